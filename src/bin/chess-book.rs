@@ -0,0 +1,78 @@
+use std::{env, fs, process};
+
+use chess::{
+    helpers::{option_value, parse_number},
+    models::Variant,
+    pgn::{parse_games, PgnGame},
+    polyglot::{BookBuilder, PolyglotBook},
+    Board,
+};
+
+const USAGE: &str = "usage:
+  chess-book build <games.pgn> <book.bin> [--min-games N] [--max-ply N]
+                   [--win-weight N] [--draw-weight N] [--loss-weight N]
+  chess-book probe <book.bin> [FEN]";
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    let outcome = match arguments.first().map(String::as_str) {
+        Some("build") if arguments.len() >= 3 => build(&arguments[1..]),
+        Some("probe") if arguments.len() >= 2 => probe(&arguments[1..]),
+        _ => Err(USAGE.to_owned()),
+    };
+    if let Err(e) = outcome {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn build(arguments: &[String]) -> Result<(), String> {
+    let text = fs::read_to_string(&arguments[0]).map_err(|e| e.to_string())?;
+    let mut builder = BookBuilder::default();
+    let mut options = arguments[2..].iter();
+    while let Some(option) = options.next() {
+        let value: u32 = parse_number(&option_value(&mut options, option)?)?;
+        match option.as_str() {
+            "--min-games" => builder.min_games = value,
+            "--max-ply" => builder.max_ply = value as usize,
+            "--win-weight" => builder.win_weight = value,
+            "--draw-weight" => builder.draw_weight = value,
+            "--loss-weight" => builder.loss_weight = value,
+            _ => return Err(format!("Unknown option: {}\n{}", option, USAGE)),
+        }
+    }
+
+    let mut games: Vec<PgnGame> = Vec::new();
+    let mut skipped = 0;
+    for game in parse_games(&text) {
+        match game {
+            Ok(game) => games.push(game),
+            Err(_) => skipped += 1,
+        }
+    }
+    let book = builder.build(&games);
+    book.save(&arguments[1])?;
+    println!(
+        "{} entries from {} games ({} skipped)",
+        book.len(),
+        games.len(),
+        skipped
+    );
+    Ok(())
+}
+
+fn probe(arguments: &[String]) -> Result<(), String> {
+    let book = PolyglotBook::open(&arguments[0])?;
+    let board = match arguments.len() {
+        1 => Board::from_variant(Variant::Standard),
+        _ => Board::try_from_fen_with_variant(&arguments[1..].join(" "), Variant::Standard)?,
+    };
+    let entries = book.entries(&board);
+    let total: u32 = entries.iter().map(|entry| entry.weight as u32).sum();
+    for entry in entries {
+        let san = board.to_san(&entry.user_move)?;
+        let share = entry.weight as f64 * 100.0 / total.max(1) as f64;
+        println!("{:8} {:6} {:5.1}%", san, entry.weight, share);
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::{env, fs, process};
+
+use chess::{
+    helpers::{option_value, parse_number, square_to_tuple, squares_to_tuples},
+    latex::{LatexOptions, LatexPackage},
+    models::{Color, Variant},
+    pgn::select_game,
+    Board,
+};
+
+const USAGE: &str = "usage:
+  chess-latex [OPTIONS] <FEN>
+  chess-latex [OPTIONS] --pgn FILE [--game N] [--diagram PLY]...
+options:
+  --package NAME    skak, xskak (default) or chessboard
+  --text            a plain-text book diagram of the FEN instead of LaTeX
+  --output FILE     write to FILE instead of standard output
+  --flip            show the board from black's side
+  --no-coordinates  leave out the file letters and rank numbers
+  --mark SQ         frame a square, e.g. e4 (repeatable)
+  --arrow FROMTO    draw an arrow, e.g. g1f3 (repeatable)
+  --diagram PLY     a diagram after PLY half-moves of the game (repeatable)";
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&arguments) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(arguments: &[String]) -> Result<(), String> {
+    let mut options = LatexOptions::default();
+    let mut text_diagram = false;
+    let mut output: Option<String> = None;
+    let mut pgn_path: Option<String> = None;
+    let mut game_number = 1;
+    let mut diagram_plies: Vec<usize> = Vec::new();
+    let mut fen_fields: Vec<&str> = Vec::new();
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--package" => {
+                options.package = LatexPackage::parse(&option_value(&mut arguments, argument)?)?
+            }
+            "--text" => text_diagram = true,
+            "--output" => output = Some(option_value(&mut arguments, argument)?),
+            "--flip" => options.orientation = Color::Black,
+            "--no-coordinates" => options.coordinates = false,
+            "--mark" => options
+                .marked
+                .push(square_to_tuple(&option_value(&mut arguments, argument)?)?),
+            "--arrow" => options
+                .arrows
+                .push(squares_to_tuples(&option_value(&mut arguments, argument)?)?),
+            "--pgn" => pgn_path = Some(option_value(&mut arguments, argument)?),
+            "--game" => game_number = parse_number(&option_value(&mut arguments, argument)?)?,
+            "--diagram" => {
+                diagram_plies.push(parse_number(&option_value(&mut arguments, argument)?)?)
+            }
+            _ if argument.starts_with("--") => {
+                return Err(format!("Unknown option: {}\n{}", argument, USAGE))
+            }
+            _ => fen_fields.push(argument),
+        }
+    }
+
+    let text = match (pgn_path, fen_fields.is_empty()) {
+        (Some(path), true) => {
+            let pgn = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+            let game = select_game(&pgn, game_number).map_err(|e| format!("{}: {}", path, e))?;
+            game.to_latex(&options, &diagram_plies)?
+        }
+        (None, false) => {
+            let board = Board::try_from_fen_with_variant(&fen_fields.join(" "), Variant::Standard)?;
+            match text_diagram {
+                true => board.to_book_diagram(options.orientation),
+                false => board.to_latex(&options),
+            }
+        }
+        _ => return Err(USAGE.to_owned()),
+    };
+    match output {
+        Some(path) => fs::write(&path, text).map_err(|e| format!("{}: {}", path, e)),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
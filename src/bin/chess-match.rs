@@ -0,0 +1,216 @@
+use std::{env, fs, path::PathBuf, process, sync::Arc, time::Duration};
+
+use chess::{
+    endgame::Tablebase,
+    helpers::{option_value, parse_number},
+    models::Variant,
+    tournament::{
+        EngineConfig, GameRecord, Opening, Sprt, SprtDecision, TimeControl, Tournament,
+        TournamentReport,
+    },
+};
+
+const USAGE: &str = "usage:
+  chess-match --engine CMD [name=NAME] [arg=ARG]... [option.NAME=VALUE]...
+              --engine CMD ... [--engine CMD ...]
+              [--tc BASE+INC | --movetime MS | --depth N | --nodes N] [--margin MS]
+              [--openings FILE.pgn|FILE.epd] [--plies N] [--rounds N] [--concurrency N]
+              [--resign SCORE MOVES] [--draw SCORE MOVES FROM_MOVE] [--max-moves N]
+              [--tablebase DIR] [--sprt ELO0 ELO1 ALPHA BETA] [--pgn FILE] [--event NAME]";
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = parse(&arguments).and_then(|tournament| run(&tournament)) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn parse(arguments: &[String]) -> Result<Tournament, String> {
+    let mut tournament = Tournament::new(Vec::new());
+    let mut openings_path: Option<String> = None;
+    let mut plies = 8;
+    let mut arguments = arguments.iter().peekable();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--engine" => {
+                let mut engine = EngineConfig::new(&option_value(&mut arguments, argument)?);
+                while let Some(setting) = arguments.next_if(|next| !next.starts_with("--")) {
+                    match setting.split_once('=') {
+                        Some(("name", name)) => engine.name = name.to_owned(),
+                        Some(("arg", arg)) => engine.arguments.push(arg.to_owned()),
+                        Some((key, option)) if key.starts_with("option.") => engine
+                            .options
+                            .push((key["option.".len()..].to_owned(), option.to_owned())),
+                        _ => return Err(format!("Unknown engine setting: {}", setting)),
+                    }
+                }
+                tournament.engines.push(engine);
+            }
+            "--tc" => {
+                tournament.time_control =
+                    TimeControl::parse(&option_value(&mut arguments, argument)?)?
+            }
+            "--movetime" => {
+                let millis = parse_number(&option_value(&mut arguments, argument)?)?;
+                tournament.time_control = TimeControl::MoveTime(Duration::from_millis(millis));
+            }
+            "--depth" => {
+                tournament.time_control =
+                    TimeControl::Depth(parse_number(&option_value(&mut arguments, argument)?)?)
+            }
+            "--nodes" => {
+                tournament.time_control =
+                    TimeControl::Nodes(parse_number(&option_value(&mut arguments, argument)?)?)
+            }
+            "--margin" => {
+                tournament.time_margin =
+                    Duration::from_millis(parse_number(&option_value(&mut arguments, argument)?)?)
+            }
+            "--openings" => openings_path = Some(option_value(&mut arguments, argument)?),
+            "--plies" => plies = parse_number(&option_value(&mut arguments, argument)?)?,
+            "--rounds" => {
+                tournament.rounds = parse_number(&option_value(&mut arguments, argument)?)?
+            }
+            "--concurrency" => {
+                tournament.concurrency = parse_number(&option_value(&mut arguments, argument)?)?
+            }
+            "--resign" => {
+                tournament.adjudication.resign_score =
+                    Some(parse_number(&option_value(&mut arguments, argument)?)?);
+                tournament.adjudication.resign_moves =
+                    parse_number(&option_value(&mut arguments, argument)?)?;
+            }
+            "--draw" => {
+                tournament.adjudication.draw_score =
+                    Some(parse_number(&option_value(&mut arguments, argument)?)?);
+                tournament.adjudication.draw_moves =
+                    parse_number(&option_value(&mut arguments, argument)?)?;
+                tournament.adjudication.draw_move_number =
+                    parse_number(&option_value(&mut arguments, argument)?)?;
+            }
+            "--max-moves" => {
+                tournament.adjudication.max_moves =
+                    Some(parse_number(&option_value(&mut arguments, argument)?)?)
+            }
+            "--tablebase" => {
+                let tablebase = Tablebase::load_dir(option_value(&mut arguments, argument)?)?;
+                tournament.adjudication.tablebase = Some(Arc::new(tablebase));
+            }
+            "--sprt" => {
+                tournament.sprt = Some(Sprt {
+                    elo0: parse_number(&option_value(&mut arguments, argument)?)?,
+                    elo1: parse_number(&option_value(&mut arguments, argument)?)?,
+                    alpha: parse_number(&option_value(&mut arguments, argument)?)?,
+                    beta: parse_number(&option_value(&mut arguments, argument)?)?,
+                })
+            }
+            "--pgn" => {
+                tournament.pgn_path = Some(PathBuf::from(option_value(&mut arguments, argument)?))
+            }
+            "--event" => tournament.event = option_value(&mut arguments, argument)?,
+            _ => return Err(format!("Unknown option: {}\n{}", argument, USAGE)),
+        }
+    }
+    if tournament.engines.len() < 2 {
+        return Err(USAGE.to_owned());
+    }
+    if let Some(path) = openings_path {
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+        tournament.openings = match path.to_lowercase().ends_with(".pgn") {
+            true => Opening::from_pgn(&text, plies),
+            false => Opening::from_epd(&text, Variant::Standard)?,
+        };
+        if tournament.openings.is_empty() {
+            return Err(format!("No openings in {}", path));
+        }
+    }
+    Ok(tournament)
+}
+
+fn run(tournament: &Tournament) -> Result<(), String> {
+    let report = tournament.run(|record, report| print_game(tournament, record, report))?;
+    println!();
+    match report.pairings.as_slice() {
+        [_] => print_match(tournament, &report),
+        _ => print_standings(&report),
+    }
+    Ok(())
+}
+
+fn print_game(tournament: &Tournament, record: &GameRecord, report: &TournamentReport) {
+    println!(
+        "Game {} ({} vs {}): {} {{{}}}",
+        record.number,
+        report.names[record.white],
+        report.names[record.black],
+        record.game.result.to_pgn(),
+        record.game.termination
+    );
+    if let [_] = report.pairings.as_slice() {
+        print_match(tournament, report);
+    }
+}
+
+fn print_match(tournament: &Tournament, report: &TournamentReport) {
+    let pairing = &report.pairings[0];
+    let score = &pairing.score;
+    println!(
+        "Score of {} vs {}: {} - {} - {}  [{:.3}] {}",
+        report.names[pairing.first],
+        report.names[pairing.second],
+        score.wins,
+        score.losses,
+        score.draws,
+        score.ratio(),
+        score.games()
+    );
+    if let Some(elo) = score.elo() {
+        println!(
+            "Elo difference: {:.1} +/- {:.1}",
+            elo.difference, elo.margin
+        );
+    }
+    if let Some(sprt) = &tournament.sprt {
+        let (lower, upper) = sprt.bounds();
+        let decision = match report.sprt {
+            Some(SprtDecision::AcceptH0) => " - H0 accepted",
+            Some(SprtDecision::AcceptH1) => " - H1 accepted",
+            None => "",
+        };
+        println!(
+            "SPRT: llr {:.2} ({:.2}, {:.2}){}",
+            sprt.llr(score),
+            lower,
+            upper,
+            decision
+        );
+    }
+}
+
+fn print_standings(report: &TournamentReport) {
+    let mut order: Vec<usize> = (0..report.names.len()).collect();
+    order.sort_by(|a, b| {
+        let points = |engine: &usize| report.standings[*engine].points();
+        points(b).total_cmp(&points(a))
+    });
+    println!(
+        "{:>3} {:20} {:>7} {:>6} {:>13}",
+        "#", "Name", "Points", "Games", "Elo"
+    );
+    for (rank, engine) in order.into_iter().enumerate() {
+        let score = &report.standings[engine];
+        let elo = match score.elo() {
+            Some(elo) => format!("{:.0} +/- {:.0}", elo.difference, elo.margin),
+            None => "-".to_owned(),
+        };
+        println!(
+            "{:>3} {:20} {:>7.1} {:>6} {:>13}",
+            rank + 1,
+            report.names[engine],
+            score.points(),
+            score.games(),
+            elo
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use std::{env, process};
+
+use chess::{models::Variant, problem::Stipulation, Board};
+
+const USAGE: &str = "usage: chess-problem <stipulation> <FEN>
+  stipulation: #N (mate), h#N (helpmate) or s#N (selfmate)";
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&arguments) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(arguments: &[String]) -> Result<(), String> {
+    if arguments.len() < 2 {
+        return Err(USAGE.to_owned());
+    }
+    let stipulation = Stipulation::parse(&arguments[0])?;
+    let board = Board::try_from_fen_with_variant(&arguments[1..].join(" "), Variant::Standard)?;
+    let report = board.solve_problem(stipulation)?;
+
+    for key in &report.keys {
+        println!("key {} ({} moves)", key.san, key.moves);
+        for defence in &key.defences {
+            let continuations: Vec<&str> = defence
+                .continuations
+                .iter()
+                .map(|(_, san)| san.as_str())
+                .collect();
+            println!("  {} {}", defence.san, continuations.join(" "));
+        }
+    }
+    for line in &report.lines {
+        println!("{}", line.join(" "));
+    }
+    let verdict = match (report.is_solved(), report.is_sound()) {
+        (false, _) => "no solution",
+        (true, false) => "cooked",
+        (true, true) if !report.duals().is_empty() => "sound, with duals",
+        (true, true) => "sound",
+    };
+    println!("{}: {} ({} nodes)", stipulation, verdict, report.nodes);
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::{env, fs, process};
+
+use chess::{
+    helpers::{option_value, parse_number, square_to_tuple, squares_to_tuples},
+    models::{Color, Variant},
+    pgn::select_game,
+    svg::{Arrow, SquareHighlight, SvgOptions},
+    Board,
+};
+
+const USAGE: &str = "usage:
+  chess-svg [OPTIONS] <FEN>
+  chess-svg [OPTIONS] --pgn FILE [--game N] [--ply N]
+options:
+  --output FILE     write to FILE instead of standard output
+  --size N          square size in pixels (default 45)
+  --flip            show the board from black's side
+  --no-coordinates  leave out the file letters and rank numbers
+  --highlight SQ    tint a square, e.g. e4 (repeatable)
+  --arrow FROMTO    draw an arrow, e.g. g1f3 (repeatable)";
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&arguments) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(arguments: &[String]) -> Result<(), String> {
+    let mut options = SvgOptions::default();
+    let mut output: Option<String> = None;
+    let mut pgn_path: Option<String> = None;
+    let mut game_number = 1;
+    let mut ply: Option<usize> = None;
+    let mut fen_fields: Vec<&str> = Vec::new();
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--output" => output = Some(option_value(&mut arguments, argument)?),
+            "--size" => {
+                options.square_size = parse_number(&option_value(&mut arguments, argument)?)?
+            }
+            "--flip" => options.orientation = Color::Black,
+            "--no-coordinates" => options.coordinates = false,
+            "--highlight" => options
+                .highlights
+                .push(SquareHighlight::new(square_to_tuple(&option_value(
+                    &mut arguments,
+                    argument,
+                )?)?)),
+            "--arrow" => {
+                let (from, to) = squares_to_tuples(&option_value(&mut arguments, argument)?)?;
+                options.arrows.push(Arrow::new(from, to));
+            }
+            "--pgn" => pgn_path = Some(option_value(&mut arguments, argument)?),
+            "--game" => game_number = parse_number(&option_value(&mut arguments, argument)?)?,
+            "--ply" => ply = Some(parse_number(&option_value(&mut arguments, argument)?)?),
+            _ if argument.starts_with("--") => {
+                return Err(format!("Unknown option: {}\n{}", argument, USAGE))
+            }
+            _ => fen_fields.push(argument),
+        }
+    }
+
+    let board = match (pgn_path, fen_fields.is_empty()) {
+        (Some(path), true) => pgn_position(&path, game_number, ply, &mut options)?,
+        (None, false) => {
+            Board::try_from_fen_with_variant(&fen_fields.join(" "), Variant::Standard)?
+        }
+        _ => return Err(USAGE.to_owned()),
+    };
+    let svg = board.to_svg(&options);
+    match output {
+        Some(path) => fs::write(&path, svg).map_err(|e| format!("{}: {}", path, e)),
+        None => {
+            print!("{}", svg);
+            Ok(())
+        }
+    }
+}
+
+/// The position after `ply` half-moves of a game, or at its end, with the move that led to it
+/// highlighted.
+fn pgn_position(
+    path: &str,
+    game_number: usize,
+    ply: Option<usize>,
+    options: &mut SvgOptions,
+) -> Result<Board, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let game = select_game(&text, game_number).map_err(|e| format!("{}: {}", path, e))?;
+    let mut positions = game.positions()?;
+    let ply = ply.unwrap_or(game.moves.len());
+    if ply > game.moves.len() {
+        return Err(format!(
+            "Game {} only has {} plies",
+            game_number,
+            game.moves.len()
+        ));
+    }
+    if ply > 0 {
+        options.last_move = Some(game.moves[ply - 1].user_move);
+    }
+    Ok(positions.swap_remove(ply))
+}
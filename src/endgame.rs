@@ -0,0 +1,1020 @@
+use std::{collections::HashMap, fs, path::Path, thread};
+
+use crate::{models::*, Board, UserMove};
+
+/// Largest number of pieces, kings included, a table can be generated for.
+pub const MAX_PIECES: usize = 4;
+
+// One byte per position: the distance to mate in plies plus one, so odd distances are wins and
+// even ones losses for the side to move, or one of the markers below.
+const DRAW: u8 = 0;
+const UNKNOWN: u8 = 254;
+const INVALID: u8 = 255;
+const MAX_DISTANCE: u8 = 252;
+const FILE_MAGIC: &[u8; 4] = b"CEGT";
+// Marks a position with a drawing exit from the table, so it can never become a loss.
+const NEVER_LOST: u8 = u8::MAX;
+
+const KING_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+const KNIGHT_OFFSETS: [(isize, isize); 8] = [
+    (2, 1),
+    (2, -1),
+    (-2, 1),
+    (-2, -1),
+    (1, 2),
+    (1, -2),
+    (-1, 2),
+    (-1, -2),
+];
+const DIAGONALS: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, 1), (1, -1)];
+const LINES: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+const PROMOTIONS: [PieceKind; 4] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Wdl {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// The result of a position with perfect play, from the side to move's point of view. Tables
+/// measure distance to mate and ignore the fifty-move rule and en passant captures.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EndgameProbe {
+    pub wdl: Wdl,
+    /// Plies until mate with best play for both sides, for won and lost positions.
+    pub dtm: Option<u32>,
+}
+
+fn decode(value: u8) -> Option<EndgameProbe> {
+    match value {
+        INVALID | UNKNOWN => None,
+        DRAW => Some(EndgameProbe {
+            wdl: Wdl::Draw,
+            dtm: None,
+        }),
+        value => {
+            let distance = value as u32 - 1;
+            let wdl = if distance % 2 == 1 {
+                Wdl::Win
+            } else {
+                Wdl::Loss
+            };
+            Some(EndgameProbe {
+                wdl,
+                dtm: Some(distance),
+            })
+        }
+    }
+}
+
+fn is_win(value: u8) -> bool {
+    value != DRAW && value < UNKNOWN && value.is_multiple_of(2)
+}
+
+/// The pieces of an ending, white before black and each side ordered king, queen, rook, bishop,
+/// knight, pawn, e.g. `KRvKN`.
+#[derive(Clone, PartialEq)]
+pub struct Material {
+    pieces: Vec<Piece>,
+}
+
+impl Material {
+    /// Parses names such as `KQvK`, `KBNK` or `KPvKP`.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid material: {}", name);
+        let name = name.replace('v', "");
+        let black_king = name
+            .get(1..)
+            .and_then(|rest| rest.find('K'))
+            .map(|index| index + 1)
+            .ok_or_else(invalid)?;
+        let mut pieces = Vec::new();
+        for (index, letter) in name.chars().enumerate() {
+            let color = if index < black_king {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let kind = match letter {
+                'K' => PieceKind::King,
+                'Q' => PieceKind::Queen,
+                'R' => PieceKind::Rook,
+                'B' => PieceKind::Bishop,
+                'N' => PieceKind::Knight,
+                'P' => PieceKind::Pawn,
+                _ => return Err(invalid()),
+            };
+            pieces.push(Piece::new(kind, color));
+        }
+        let kings = pieces
+            .iter()
+            .filter(|piece| piece.kind == PieceKind::King)
+            .count();
+        if !name.starts_with('K') || kings != 2 || pieces.len() > MAX_PIECES {
+            return Err(invalid());
+        }
+        Ok(Self::from_pieces(pieces))
+    }
+
+    fn from_pieces(mut pieces: Vec<Piece>) -> Self {
+        pieces.sort_by_key(sort_key);
+        Material { pieces }
+    }
+
+    pub fn name(&self) -> String {
+        let mut name = String::new();
+        for (index, piece) in self.pieces.iter().enumerate() {
+            if index > 0 && piece.kind == PieceKind::King {
+                name.push('v');
+            }
+            name.push(crate::san::piece_letter(piece.kind));
+        }
+        name
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// The same ending with the colours swapped.
+    pub fn mirrored(&self) -> Self {
+        Self::from_pieces(self.pieces.iter().map(mirror_piece).collect())
+    }
+}
+
+fn sort_key(piece: &Piece) -> (usize, usize) {
+    let color = match piece.color {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    let kind = match piece.kind {
+        PieceKind::King => 0,
+        PieceKind::Queen => 1,
+        PieceKind::Rook => 2,
+        PieceKind::Bishop => 3,
+        PieceKind::Knight => 4,
+        PieceKind::Pawn => 5,
+    };
+    (color, kind)
+}
+
+fn mirror_piece(piece: &Piece) -> Piece {
+    Piece::new(piece.kind, piece.color.get_opposite_color())
+}
+
+/// Distance-to-mate values for every placement of one material with either side to move.
+pub struct EndgameTable {
+    material: Material,
+    values: Vec<u8>,
+}
+
+impl EndgameTable {
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn value(&self, side_to_move: Color, squares: &[usize]) -> u8 {
+        self.values[index(self.material.pieces.len(), side_to_move, squares)]
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let name = self.material.name();
+        let mut bytes = Vec::with_capacity(self.values.len() + name.len() + 5);
+        bytes.extend(FILE_MAGIC);
+        bytes.push(name.len() as u8);
+        bytes.extend(name.as_bytes());
+        bytes.extend(&self.values);
+        fs::write(path.as_ref(), bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let bytes = fs::read(path.as_ref()).map_err(|e| e.to_string())?;
+        let invalid = || format!("Not an endgame table: {}", path.as_ref().display());
+        if bytes.len() < 5 || &bytes[..4] != FILE_MAGIC {
+            return Err(invalid());
+        }
+        let name_end = 5 + bytes[4] as usize;
+        let name = bytes
+            .get(5..name_end)
+            .and_then(|name| std::str::from_utf8(name).ok())
+            .ok_or_else(invalid)?;
+        let material = Material::parse(name)?;
+        let values = bytes[name_end..].to_vec();
+        if values.len() != table_size(material.pieces.len()) {
+            return Err(invalid());
+        }
+        Ok(EndgameTable { material, values })
+    }
+}
+
+fn table_size(piece_count: usize) -> usize {
+    2 << (6 * piece_count)
+}
+
+fn index(piece_count: usize, side_to_move: Color, squares: &[usize]) -> usize {
+    let side = match side_to_move {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    squares[..piece_count]
+        .iter()
+        .fold(side, |index, square| index * 64 + square)
+}
+
+fn decode_index(piece_count: usize, mut index: usize) -> (Color, [usize; MAX_PIECES]) {
+    let mut squares = [0; MAX_PIECES];
+    for slot in (0..piece_count).rev() {
+        squares[slot] = index % 64;
+        index /= 64;
+    }
+    let side_to_move = if index == 0 {
+        Color::White
+    } else {
+        Color::Black
+    };
+    (side_to_move, squares)
+}
+
+/// A collection of generated or loaded endgame tables. Each table also answers for the ending
+/// with the colours swapped.
+#[derive(Default)]
+pub struct Tablebase {
+    tables: HashMap<String, EndgameTable>,
+}
+
+impl Tablebase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates the table for `material`, e.g. `KRvK`, along with every smaller ending it can
+    /// turn into through captures and promotions.
+    pub fn generate(&mut self, material: &str) -> Result<(), String> {
+        let material = Material::parse(material)?;
+        self.generate_material(&material);
+        Ok(())
+    }
+
+    /// Generates every ending with up to `piece_count` pieces. Four pieces take a while and
+    /// 32 MB per table.
+    pub fn generate_all(&mut self, piece_count: usize) -> Result<(), String> {
+        if !(2..=MAX_PIECES).contains(&piece_count) {
+            return Err(format!("Tables have 2 to {} pieces", MAX_PIECES));
+        }
+        let kinds = [
+            PieceKind::Queen,
+            PieceKind::Rook,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+            PieceKind::Pawn,
+        ];
+        let mut extras: Vec<Vec<Piece>> = vec![Vec::new()];
+        for _ in 2..piece_count {
+            let mut longer = Vec::new();
+            for extra in &extras {
+                for kind in kinds {
+                    for color in [Color::White, Color::Black] {
+                        let mut pieces = extra.clone();
+                        pieces.push(Piece::new(kind, color));
+                        longer.push(pieces);
+                    }
+                }
+            }
+            extras.extend(longer);
+        }
+        for extra in extras {
+            let mut pieces = vec![
+                Piece::new(PieceKind::King, Color::White),
+                Piece::new(PieceKind::King, Color::Black),
+            ];
+            pieces.extend(extra);
+            self.generate_material(&Material::from_pieces(pieces));
+        }
+        Ok(())
+    }
+
+    fn generate_material(&mut self, material: &Material) {
+        if self.find(material).is_some() {
+            return;
+        }
+        for child in exit_materials(material) {
+            self.generate_material(&child);
+        }
+        let table = generate_table(material, self);
+        self.insert(table);
+    }
+
+    pub fn insert(&mut self, table: EndgameTable) {
+        self.tables.insert(table.material.name(), table);
+    }
+
+    pub fn contains(&self, material: &str) -> bool {
+        Material::parse(material).is_ok_and(|material| self.find(&material).is_some())
+    }
+
+    /// Names of the tables held, without their colour-swapped counterparts.
+    pub fn materials(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tables.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Writes every table into `directory` as `<material>.cegt`.
+    pub fn save_dir(&self, directory: impl AsRef<Path>) -> Result<(), String> {
+        fs::create_dir_all(directory.as_ref()).map_err(|e| e.to_string())?;
+        for (name, table) in &self.tables {
+            table.save(directory.as_ref().join(format!("{}.cegt", name)))?;
+        }
+        Ok(())
+    }
+
+    pub fn load_dir(directory: impl AsRef<Path>) -> Result<Self, String> {
+        let mut tablebase = Self::new();
+        let entries = fs::read_dir(directory.as_ref()).map_err(|e| e.to_string())?;
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "cegt")
+            {
+                tablebase.insert(EndgameTable::load(&path)?);
+            }
+        }
+        Ok(tablebase)
+    }
+
+    fn find(&self, material: &Material) -> Option<(&EndgameTable, bool)> {
+        if let Some(table) = self.tables.get(&material.name()) {
+            return Some((table, false));
+        }
+        self.tables
+            .get(&material.mirrored().name())
+            .map(|table| (table, true))
+    }
+
+    /// Looks up a standard chess position without castling rights.
+    pub fn probe(&self, board: &Board) -> Option<EndgameProbe> {
+        let rights = &board.castle_rights;
+        if board.variant != Variant::Standard
+            || rights.white_king_side
+            || rights.white_queen_side
+            || rights.black_king_side
+            || rights.black_queen_side
+        {
+            return None;
+        }
+        let mut placed: Vec<(Piece, usize)> = Vec::new();
+        for (row, pieces) in board.board.iter().enumerate() {
+            for (column, square) in pieces.iter().enumerate() {
+                if let Some(piece) = square {
+                    placed.push((*piece, row * 8 + column));
+                }
+            }
+        }
+        if placed.len() > MAX_PIECES {
+            return None;
+        }
+        let material = Material::from_pieces(placed.iter().map(|(piece, _)| *piece).collect());
+        let (table, mirrored) = self.find(&material)?;
+        if mirrored {
+            placed = placed
+                .iter()
+                .map(|(piece, square)| (mirror_piece(piece), square ^ 56))
+                .collect();
+        }
+        placed.sort_by_key(|(piece, _)| sort_key(piece));
+        let squares: Vec<usize> = placed.iter().map(|(_, square)| *square).collect();
+        let side_to_move = if mirrored {
+            board.player_turn.get_opposite_color()
+        } else {
+            board.player_turn
+        };
+        decode(table.value(side_to_move, &squares))
+    }
+
+    /// The move that wins fastest, loses slowest, or keeps the draw.
+    pub fn best_move(&self, board: &Board) -> Option<UserMove> {
+        self.probe(board)?;
+        let mut best: Option<(i64, UserMove)> = None;
+        for m in board.legal_moves() {
+            let mut child = *board;
+            child.make_move(&m.piece_move);
+            let probe = match child.game_status() {
+                crate::GameStatus::Checkmate(_) => EndgameProbe {
+                    wdl: Wdl::Loss,
+                    dtm: Some(0),
+                },
+                crate::GameStatus::Draw => EndgameProbe {
+                    wdl: Wdl::Draw,
+                    dtm: None,
+                },
+                _ => self.probe(&child)?,
+            };
+            // Ranked from the mover's point of view: quick wins first, slow losses last.
+            let rank = match (probe.wdl, probe.dtm) {
+                (Wdl::Loss, Some(distance)) => 1_000 - distance as i64,
+                (Wdl::Win, Some(distance)) => -1_000 + distance as i64,
+                _ => 0,
+            };
+            if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+                best = Some((rank, m.piece_move));
+            }
+        }
+        best.map(|(_, best_move)| best_move)
+    }
+}
+
+/// The endings a material can turn into with one capture or promotion.
+fn exit_materials(material: &Material) -> Vec<Material> {
+    let pieces = &material.pieces;
+    let mut children = Vec::new();
+    let mut add = |child: Vec<Piece>| {
+        let child = Material::from_pieces(child);
+        if !children.contains(&child) {
+            children.push(child);
+        }
+    };
+    for captured in 0..pieces.len() {
+        if pieces[captured].kind != PieceKind::King {
+            add(without(pieces, captured));
+        }
+    }
+    for (pawn, piece) in pieces.iter().enumerate() {
+        if piece.kind != PieceKind::Pawn {
+            continue;
+        }
+        for kind in PROMOTIONS {
+            let mut promoted = pieces.clone();
+            promoted[pawn].kind = kind;
+            for (captured, victim) in pieces.iter().enumerate() {
+                if victim.color != piece.color && victim.kind != PieceKind::King {
+                    add(without(&promoted, captured));
+                }
+            }
+            add(promoted);
+        }
+    }
+    children
+}
+
+fn without(pieces: &[Piece], removed: usize) -> Vec<Piece> {
+    pieces
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != removed)
+        .map(|(_, piece)| *piece)
+        .collect()
+}
+
+/// Where a capture or promotion leads: the table of the resulting material and how to arrange
+/// the remaining pieces for it.
+struct Exit<'a> {
+    captured: Option<usize>,
+    promotion: Option<(usize, PieceKind)>,
+    table: &'a EndgameTable,
+    /// For each slot of `table`, the index of the piece in the post-move piece list.
+    order: Vec<usize>,
+    mirrored: bool,
+}
+
+impl Exit<'_> {
+    fn value(&self, side_to_move: Color, squares: &[usize]) -> u8 {
+        let mut arranged = [0; MAX_PIECES];
+        for (slot, piece) in self.order.iter().enumerate() {
+            arranged[slot] = if self.mirrored {
+                squares[*piece] ^ 56
+            } else {
+                squares[*piece]
+            };
+        }
+        let side_to_move = if self.mirrored {
+            side_to_move.get_opposite_color()
+        } else {
+            side_to_move
+        };
+        self.table
+            .value(side_to_move, &arranged[..self.order.len()])
+    }
+}
+
+enum Successor {
+    InTable,
+    Exit(u8),
+}
+
+/// Move generation over a bare list of pieces and squares, fast enough to visit every
+/// placement of a four-piece ending.
+struct Generator<'a> {
+    pieces: Vec<Piece>,
+    exits: Vec<Exit<'a>>,
+    attacks: AttackTables,
+}
+
+impl<'a> Generator<'a> {
+    fn new(material: &Material, tablebase: &'a Tablebase) -> Self {
+        let pieces = material.pieces.clone();
+        let mut exits = Vec::new();
+        let mut add_exit = |captured: Option<usize>, promotion: Option<(usize, PieceKind)>| {
+            let mut child = pieces.clone();
+            if let Some((pawn, kind)) = promotion {
+                child[pawn].kind = kind;
+            }
+            if let Some(captured) = captured {
+                child.remove(captured);
+            }
+            let material = Material::from_pieces(child.clone());
+            let (table, mirrored) = tablebase
+                .find(&material)
+                .expect("smaller endings are generated first");
+            let mut order: Vec<usize> = (0..child.len()).collect();
+            order.sort_by_key(|index| match mirrored {
+                true => sort_key(&mirror_piece(&child[*index])),
+                false => sort_key(&child[*index]),
+            });
+            exits.push(Exit {
+                captured,
+                promotion,
+                table,
+                order,
+                mirrored,
+            });
+        };
+        for (index, piece) in pieces.iter().enumerate() {
+            if piece.kind != PieceKind::King {
+                add_exit(Some(index), None);
+            }
+            if piece.kind == PieceKind::Pawn {
+                for kind in PROMOTIONS {
+                    add_exit(None, Some((index, kind)));
+                    for (captured, victim) in pieces.iter().enumerate() {
+                        if victim.color != piece.color && victim.kind != PieceKind::King {
+                            add_exit(Some(captured), Some((index, kind)));
+                        }
+                    }
+                }
+            }
+        }
+        Generator {
+            pieces,
+            exits,
+            attacks: AttackTables::new(),
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.pieces.len()
+    }
+
+    fn king(&self, color: Color) -> usize {
+        self.pieces
+            .iter()
+            .position(|piece| piece.kind == PieceKind::King && piece.color == color)
+            .unwrap()
+    }
+
+    fn is_valid(&self, side_to_move: Color, squares: &[usize]) -> bool {
+        let count = self.count();
+        for first in 0..count {
+            for second in first + 1..count {
+                if squares[first] == squares[second] {
+                    return false;
+                }
+            }
+            let row = squares[first] / 8;
+            if self.pieces[first].kind == PieceKind::Pawn && (row == 0 || row == 7) {
+                return false;
+            }
+        }
+        let waiting = side_to_move.get_opposite_color();
+        !self.is_attacked(squares, squares[self.king(waiting)], side_to_move, None)
+    }
+
+    fn occupancy(&self, squares: &[usize], skip: Option<usize>) -> u64 {
+        (0..self.count())
+            .filter(|index| Some(*index) != skip)
+            .fold(0, |occupancy, index| occupancy | 1 << squares[index])
+    }
+
+    fn is_attacked(
+        &self,
+        squares: &[usize],
+        target: usize,
+        attacker: Color,
+        skip: Option<usize>,
+    ) -> bool {
+        let occupancy = self.occupancy(squares, skip);
+        (0..self.count()).any(|index| {
+            Some(index) != skip
+                && self.pieces[index].color == attacker
+                && self
+                    .attacks
+                    .of(&self.pieces[index], squares[index], occupancy)
+                    & 1 << target
+                    != 0
+        })
+    }
+
+    fn piece_on(&self, squares: &[usize], square: usize) -> Option<usize> {
+        (0..self.count()).find(|index| squares[*index] == square)
+    }
+
+    /// Calls `visit` with the outcome of every legal move in a valid position.
+    fn for_each_successor(
+        &self,
+        side_to_move: Color,
+        squares: &[usize],
+        mut visit: impl FnMut(Successor),
+    ) {
+        let count = self.count();
+        let occupancy = self.occupancy(squares, None);
+        let own_king = self.king(side_to_move);
+        let opponent = side_to_move.get_opposite_color();
+        for mover in 0..count {
+            let piece = self.pieces[mover];
+            if piece.color != side_to_move {
+                continue;
+            }
+            let from = squares[mover];
+            let targets = match piece.kind {
+                PieceKind::Pawn => {
+                    pawn_pushes(piece.color, from, occupancy)
+                        | self.attacks.of(&piece, from, occupancy)
+                            & self.enemy_occupancy(squares, side_to_move)
+                }
+                _ => {
+                    self.attacks.of(&piece, from, occupancy)
+                        & !self.own_occupancy(squares, side_to_move)
+                }
+            };
+            let mut remaining = targets;
+            while remaining != 0 {
+                let to = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                let captured = self.piece_on(squares, to);
+                if captured.is_some_and(|index| self.pieces[index].kind == PieceKind::King) {
+                    continue;
+                }
+                let mut moved = [0; MAX_PIECES];
+                moved[..count].copy_from_slice(&squares[..count]);
+                moved[mover] = to;
+                if self.is_attacked(&moved, moved[own_king], opponent, captured) {
+                    continue;
+                }
+                let promotes = piece.kind == PieceKind::Pawn && (to / 8 == 0 || to / 8 == 7);
+                if promotes {
+                    for kind in PROMOTIONS {
+                        visit(self.exit_value(&moved, opponent, captured, Some((mover, kind))));
+                    }
+                } else if captured.is_some() {
+                    visit(self.exit_value(&moved, opponent, captured, None));
+                } else {
+                    visit(Successor::InTable);
+                }
+            }
+        }
+    }
+
+    fn exit_value(
+        &self,
+        moved: &[usize],
+        side_to_move: Color,
+        captured: Option<usize>,
+        promotion: Option<(usize, PieceKind)>,
+    ) -> Successor {
+        let exit = self
+            .exits
+            .iter()
+            .find(|exit| exit.captured == captured && exit.promotion == promotion)
+            .expect("every capture and promotion has an exit");
+        let mut remaining = [0; MAX_PIECES];
+        let mut length = 0;
+        for index in (0..self.count()).filter(|index| Some(*index) != captured) {
+            remaining[length] = moved[index];
+            length += 1;
+        }
+        Successor::Exit(exit.value(side_to_move, &remaining[..length]))
+    }
+
+    fn own_occupancy(&self, squares: &[usize], color: Color) -> u64 {
+        (0..self.count())
+            .filter(|index| self.pieces[*index].color == color)
+            .fold(0, |occupancy, index| occupancy | 1 << squares[index])
+    }
+
+    fn enemy_occupancy(&self, squares: &[usize], color: Color) -> u64 {
+        self.own_occupancy(squares, color.get_opposite_color())
+    }
+
+    /// Calls `visit` with every valid position, side to move included, whose mover could have
+    /// reached this one with a move that keeps the material unchanged.
+    fn for_each_predecessor(
+        &self,
+        side_to_move: Color,
+        squares: &[usize],
+        mut visit: impl FnMut(usize),
+    ) {
+        let count = self.count();
+        let occupancy = self.occupancy(squares, None);
+        let mover_color = side_to_move.get_opposite_color();
+        for mover in 0..count {
+            let piece = self.pieces[mover];
+            if piece.color != mover_color {
+                continue;
+            }
+            let to = squares[mover];
+            let origins = match piece.kind {
+                PieceKind::Pawn => pawn_origins(piece.color, to, occupancy),
+                _ => self.attacks.of(&piece, to, occupancy) & !occupancy,
+            };
+            let mut remaining = origins;
+            while remaining != 0 {
+                let from = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                let mut previous = [0; MAX_PIECES];
+                previous[..count].copy_from_slice(&squares[..count]);
+                previous[mover] = from;
+                let waiting_king = previous[self.king(side_to_move)];
+                if !self.is_attacked(&previous, waiting_king, mover_color, None) {
+                    visit(index(count, mover_color, &previous));
+                }
+            }
+        }
+    }
+}
+
+fn offset_square(square: usize, (rows, columns): (isize, isize)) -> Option<usize> {
+    let row = (square / 8) as isize + rows;
+    let column = (square % 8) as isize + columns;
+    ((0..8).contains(&row) && (0..8).contains(&column)).then(|| (row * 8 + column) as usize)
+}
+
+/// Attack sets precomputed per square, sliders being resolved against the occupancy by
+/// cutting each ray at its first blocker.
+struct AttackTables {
+    king: [u64; 64],
+    knight: [u64; 64],
+    pawn: [[u64; 64]; 2],
+    rays: [[u64; 64]; 8],
+}
+
+impl AttackTables {
+    fn new() -> Self {
+        let mut tables = AttackTables {
+            king: [0; 64],
+            knight: [0; 64],
+            pawn: [[0; 64]; 2],
+            rays: [[0; 64]; 8],
+        };
+        let steps = |square: usize, offsets: &[(isize, isize)]| {
+            offsets
+                .iter()
+                .filter_map(|offset| offset_square(square, *offset))
+                .fold(0u64, |set, target| set | 1 << target)
+        };
+        for square in 0..64 {
+            tables.king[square] = steps(square, &KING_OFFSETS);
+            tables.knight[square] = steps(square, &KNIGHT_OFFSETS);
+            tables.pawn[0][square] = steps(square, &[(1, -1), (1, 1)]);
+            tables.pawn[1][square] = steps(square, &[(-1, -1), (-1, 1)]);
+            for (ray, direction) in DIAGONALS.iter().chain(&LINES).enumerate() {
+                let mut current = square;
+                while let Some(next) = offset_square(current, *direction) {
+                    tables.rays[ray][square] |= 1 << next;
+                    current = next;
+                }
+            }
+        }
+        tables
+    }
+
+    fn slide(&self, rays: std::ops::Range<usize>, square: usize, occupancy: u64) -> u64 {
+        let mut set = 0;
+        for ray in rays {
+            let mut attacked = self.rays[ray][square];
+            let blockers = attacked & occupancy;
+            if blockers != 0 {
+                let (rows, columns) = match ray {
+                    0..4 => DIAGONALS[ray],
+                    _ => LINES[ray - 4],
+                };
+                let first = match rows * 8 + columns > 0 {
+                    true => blockers.trailing_zeros() as usize,
+                    false => 63 - blockers.leading_zeros() as usize,
+                };
+                attacked ^= self.rays[ray][first];
+            }
+            set |= attacked;
+        }
+        set
+    }
+
+    /// Squares a piece attacks, which for pawns are only the diagonal capture squares.
+    fn of(&self, piece: &Piece, square: usize, occupancy: u64) -> u64 {
+        match piece.kind {
+            PieceKind::King => self.king[square],
+            PieceKind::Knight => self.knight[square],
+            PieceKind::Bishop => self.slide(0..4, square, occupancy),
+            PieceKind::Rook => self.slide(4..8, square, occupancy),
+            PieceKind::Queen => self.slide(0..8, square, occupancy),
+            PieceKind::Pawn => match piece.color {
+                Color::White => self.pawn[0][square],
+                Color::Black => self.pawn[1][square],
+            },
+        }
+    }
+}
+
+fn pawn_direction(color: Color) -> (isize, usize) {
+    match color {
+        Color::White => (1, 1),
+        Color::Black => (-1, 6),
+    }
+}
+
+fn pawn_pushes(color: Color, square: usize, occupancy: u64) -> u64 {
+    let (direction, start_row) = pawn_direction(color);
+    let mut pushes = 0;
+    if let Some(single) = offset_square(square, (direction, 0)) {
+        if occupancy & 1 << single == 0 {
+            pushes |= 1 << single;
+            if square / 8 == start_row {
+                let double = offset_square(single, (direction, 0)).unwrap();
+                if occupancy & 1 << double == 0 {
+                    pushes |= 1 << double;
+                }
+            }
+        }
+    }
+    pushes
+}
+
+fn pawn_origins(color: Color, square: usize, occupancy: u64) -> u64 {
+    let (direction, start_row) = pawn_direction(color);
+    let mut origins = 0;
+    if let Some(single) = offset_square(square, (-direction, 0)) {
+        let row = single / 8;
+        if occupancy & 1 << single == 0 && row != 0 && row != 7 {
+            origins |= 1 << single;
+            if let Some(double) = offset_square(single, (-direction, 0)) {
+                if double / 8 == start_row && occupancy & 1 << double == 0 {
+                    origins |= 1 << double;
+                }
+            }
+        }
+    }
+    origins
+}
+
+/// Retrograde analysis: mates and exits from the table seed the values, then each pass walks
+/// back from the positions decided at the previous distance. A position is won as soon as one
+/// move reaches a lost position, and lost once every move reaches a won one.
+fn generate_table(material: &Material, tablebase: &Tablebase) -> EndgameTable {
+    let generator = Generator::new(material, tablebase);
+    let count = generator.count();
+    let size = table_size(count);
+    let mut values = vec![UNKNOWN; size];
+    // Moves not yet known to lose, and the loss distance forced by losing exits.
+    let mut remaining = vec![0u8; size];
+    let mut exit_floor = vec![0u8; size];
+
+    let workers = thread::available_parallelism().map_or(1, |workers| workers.get());
+    let chunk = size.div_ceil(workers);
+    thread::scope(|scope| {
+        let chunks = values
+            .chunks_mut(chunk)
+            .zip(remaining.chunks_mut(chunk))
+            .zip(exit_floor.chunks_mut(chunk))
+            .enumerate();
+        for (number, ((values, remaining), exit_floor)) in chunks {
+            let generator = &generator;
+            scope.spawn(move || {
+                for offset in 0..values.len() {
+                    let (value, moves, floor) = initial_value(generator, number * chunk + offset);
+                    values[offset] = value;
+                    remaining[offset] = moves;
+                    exit_floor[offset] = floor;
+                }
+            });
+        }
+    });
+
+    // Positions by value, so each pass only visits the ones decided at its distance. Entries
+    // left behind when a pending win is improved are skipped.
+    let mut levels: Vec<Vec<u32>> = vec![Vec::new(); 256];
+    for (position, value) in values.iter().enumerate() {
+        if *value != DRAW && *value < UNKNOWN {
+            levels[*value as usize].push(position as u32);
+        }
+    }
+    for distance in 0..=MAX_DISTANCE {
+        let level = std::mem::take(&mut levels[distance as usize + 1]);
+        let lost = distance.is_multiple_of(2);
+        for position in level {
+            let position = position as usize;
+            if values[position] != distance + 1 {
+                continue;
+            }
+            let (side_to_move, squares) = decode_index(count, position);
+            generator.for_each_predecessor(side_to_move, &squares, |previous| {
+                let value = values[previous];
+                if lost {
+                    // A win found now is faster than one through an exit that is still pending.
+                    if value == UNKNOWN || is_win(value) && value > distance + 2 {
+                        values[previous] = distance + 2;
+                        levels[distance as usize + 2].push(previous as u32);
+                    }
+                } else if value == UNKNOWN && remaining[previous] != NEVER_LOST {
+                    remaining[previous] -= 1;
+                    if remaining[previous] == 0 {
+                        let loss = (distance + 1).max(exit_floor[previous]);
+                        values[previous] = loss + 1;
+                        levels[loss as usize + 1].push(previous as u32);
+                    }
+                }
+            });
+        }
+    }
+
+    for value in values.iter_mut() {
+        if *value == UNKNOWN {
+            *value = DRAW;
+        }
+    }
+    EndgameTable {
+        material: material.clone(),
+        values,
+    }
+}
+
+/// The value a position gets before any backward pass, with its count of moves that stay in
+/// the table and the loss distance its losing exits force.
+fn initial_value(generator: &Generator, position: usize) -> (u8, u8, u8) {
+    let count = generator.count();
+    let (side_to_move, squares) = decode_index(count, position);
+    if !generator.is_valid(side_to_move, &squares[..count]) {
+        return (INVALID, 0, 0);
+    }
+    let mut legal_moves = 0;
+    let mut in_table: u8 = 0;
+    let mut fastest_win: Option<u8> = None;
+    let mut floor: u8 = 0;
+    let mut drawing_exit = false;
+    generator.for_each_successor(side_to_move, &squares[..count], |successor| {
+        legal_moves += 1;
+        match successor {
+            Successor::InTable => in_table += 1,
+            Successor::Exit(value) => match decode(value) {
+                Some(EndgameProbe {
+                    wdl: Wdl::Loss,
+                    dtm: Some(distance),
+                }) => {
+                    let win = distance as u8 + 1;
+                    fastest_win = Some(fastest_win.map_or(win, |fastest| fastest.min(win)));
+                }
+                Some(EndgameProbe {
+                    wdl: Wdl::Win,
+                    dtm: Some(distance),
+                }) => floor = floor.max(distance as u8 + 1),
+                _ => drawing_exit = true,
+            },
+        }
+    });
+
+    let in_check = generator.is_attacked(
+        &squares[..count],
+        squares[generator.king(side_to_move)],
+        side_to_move.get_opposite_color(),
+        None,
+    );
+    if legal_moves == 0 {
+        return match in_check {
+            true => (1, 0, 0),
+            false => (DRAW, 0, 0),
+        };
+    }
+    if let Some(win) = fastest_win {
+        return (win + 1, in_table, floor);
+    }
+    if drawing_exit {
+        return (UNKNOWN, NEVER_LOST, floor);
+    }
+    if in_table == 0 {
+        return (floor + 1, 0, floor);
+    }
+    (UNKNOWN, in_table, floor)
+}
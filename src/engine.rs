@@ -0,0 +1,234 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryIter},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    endgame::Tablebase,
+    search::{InfoCallback, SearchInfo, SearchLimits, SearchResult, Searcher},
+    Board,
+};
+
+pub type DoneCallback = Box<dyn FnOnce(&SearchResult) + Send>;
+
+/// The searcher behind the UCI and XBoard front ends. Searches run on their own thread, which
+/// owns the `Searcher` while it thinks and hands it back when joined, so a protocol loop stays
+/// free to read `stop`, `ponderhit` or `?` from the GUI.
+pub struct Engine {
+    searcher: Option<Searcher>,
+    search_thread: Option<JoinHandle<(Searcher, SearchResult)>>,
+    stop: Arc<AtomicBool>,
+    ponder: Arc<AtomicBool>,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        let searcher = Searcher::new(SearchLimits::default());
+        Engine {
+            stop: searcher.stop_flag(),
+            ponder: searcher.ponder_flag(),
+            searcher: Some(searcher),
+            search_thread: None,
+        }
+    }
+
+    /// Starts searching `board` in the background. `on_info` sees every completed iteration and
+    /// `on_done` the final result, both on the search thread.
+    pub fn start(
+        &mut self,
+        board: Board,
+        limits: SearchLimits,
+        ponder: bool,
+        on_info: Option<InfoCallback>,
+        on_done: DoneCallback,
+    ) {
+        self.stop();
+        let mut searcher = self.take_searcher();
+        searcher.set_limits(limits);
+        searcher.set_info_callback(on_info);
+        self.stop.store(false, Ordering::Relaxed);
+        self.ponder.store(ponder, Ordering::Relaxed);
+        self.search_thread = Some(thread::spawn(move || {
+            let result = searcher.search(&board);
+            on_done(&result);
+            (searcher, result)
+        }));
+    }
+
+    /// Starts searching `board` in the background and returns a handle that streams the
+    /// search's progress and controls it, for front ends that must stay responsive meanwhile.
+    pub fn start_search(&mut self, board: &Board, limits: SearchLimits) -> SearchHandle {
+        self.start_with_handle(board, limits, false)
+    }
+
+    /// Like `start_search`, but ignoring time limits until `SearchHandle::ponderhit`.
+    pub fn start_ponder(&mut self, board: &Board, limits: SearchLimits) -> SearchHandle {
+        self.start_with_handle(board, limits, true)
+    }
+
+    fn start_with_handle(
+        &mut self,
+        board: &Board,
+        limits: SearchLimits,
+        ponder: bool,
+    ) -> SearchHandle {
+        let (info_sender, info) = mpsc::channel();
+        let (result_sender, result) = mpsc::channel();
+        let on_info = move |info: &SearchInfo| {
+            let _ = info_sender.send(info.clone());
+        };
+        let on_done = move |result: &SearchResult| {
+            let _ = result_sender.send(result.clone());
+        };
+        self.start(
+            *board,
+            limits,
+            ponder,
+            Some(Box::new(on_info)),
+            Box::new(on_done),
+        );
+        SearchHandle {
+            stop: self.stop.clone(),
+            ponder: self.ponder.clone(),
+            info,
+            result,
+            finished: None,
+        }
+    }
+
+    /// Aborts the running search, if any, and returns its result once it has finished.
+    pub fn stop(&mut self) -> Option<SearchResult> {
+        if self.search_thread.is_some() {
+            self.stop.store(true, Ordering::Relaxed);
+            self.ponder.store(false, Ordering::Relaxed);
+        }
+        self.wait()
+    }
+
+    /// Waits for the running search, if any, to finish on its own.
+    pub fn wait(&mut self) -> Option<SearchResult> {
+        let handle = self.search_thread.take()?;
+        let (searcher, result) = handle.join().expect("search thread panicked");
+        self.searcher = Some(searcher);
+        Some(result)
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search_thread
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Switches a ponder search over to the normal time limits, counted from now.
+    pub fn ponderhit(&self) {
+        self.ponder.store(false, Ordering::Relaxed);
+    }
+
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn ponder_flag(&self) -> Arc<AtomicBool> {
+        self.ponder.clone()
+    }
+
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.stop();
+        self.searcher_mut().set_hash_size(size_mb);
+    }
+
+    pub fn clear_hash(&mut self) {
+        self.stop();
+        self.searcher_mut().clear_hash();
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.stop();
+        self.searcher_mut().set_threads(threads);
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Option<Arc<Tablebase>>) {
+        self.stop();
+        self.searcher_mut().set_tablebase(tablebase);
+    }
+
+    /// The game's positions before the one the next search starts from, oldest first.
+    pub fn set_history(&mut self, history: &[Board]) {
+        self.stop();
+        self.searcher_mut().set_history(history);
+    }
+
+    fn take_searcher(&mut self) -> Searcher {
+        self.searcher
+            .take()
+            .expect("searcher is idle between searches")
+    }
+
+    fn searcher_mut(&mut self) -> &mut Searcher {
+        self.searcher
+            .as_mut()
+            .expect("searcher is idle between searches")
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A search running on the engine's thread. Progress arrives as one `SearchInfo` per line of
+/// every completed iteration. Dropping the handle leaves the search running until it ends on
+/// its own or the engine starts another; `stop` and `ponderhit` act on whichever search the
+/// engine is running.
+pub struct SearchHandle {
+    stop: Arc<AtomicBool>,
+    ponder: Arc<AtomicBool>,
+    info: Receiver<SearchInfo>,
+    result: Receiver<SearchResult>,
+    finished: Option<SearchResult>,
+}
+
+impl SearchHandle {
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.ponder.store(false, Ordering::Relaxed);
+    }
+
+    /// Switches a ponder search over to the normal time limits, counted from now.
+    pub fn ponderhit(&self) {
+        self.ponder.store(false, Ordering::Relaxed);
+    }
+
+    /// Progress reported since the last call, without blocking.
+    pub fn updates(&self) -> TryIter<'_, SearchInfo> {
+        self.info.try_iter()
+    }
+
+    /// The channel progress reports arrive on, for waiting on them.
+    pub fn info_receiver(&self) -> &Receiver<SearchInfo> {
+        &self.info
+    }
+
+    pub fn is_finished(&mut self) -> bool {
+        if self.finished.is_none() {
+            self.finished = self.result.try_recv().ok();
+        }
+        self.finished.is_some()
+    }
+
+    /// Waits for the search to end and returns its result, with the best move.
+    pub fn join(mut self) -> SearchResult {
+        match self.finished.take() {
+            Some(result) => result,
+            None => self
+                .result
+                .recv()
+                .expect("search thread sends its result before exiting"),
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use crate::{helpers::*, models::*, moves, Board};
+
+const PHASE_WEIGHTS: [i32; 6] = [0, 4, 2, 1, 1, 0];
+const TOTAL_PHASE: i32 = 24;
+
+const MATERIAL: [(i32, i32); 6] = [
+    (0, 0),
+    (1025, 936),
+    (477, 512),
+    (365, 297),
+    (337, 281),
+    (82, 94),
+];
+
+const MOBILITY: [(i32, i32); 6] = [(0, 0), (1, 2), (2, 4), (5, 5), (4, 4), (0, 0)];
+const MOBILITY_BASELINE: [i32; 6] = [0, 14, 7, 7, 4, 0];
+
+const DOUBLED_PAWN: (i32, i32) = (-10, -20);
+const ISOLATED_PAWN: (i32, i32) = (-10, -15);
+const PASSED_PAWN: [(i32, i32); 8] = [
+    (0, 0),
+    (0, 10),
+    (5, 15),
+    (10, 25),
+    (20, 45),
+    (35, 75),
+    (60, 120),
+    (0, 0),
+];
+
+const PAWN_SHIELD: i32 = 12;
+const KING_ZONE_ATTACK: i32 = -8;
+
+// Piece-square tables are written from white's point of view with the eighth rank first.
+#[rustfmt::skip]
+const KING_MG: [i32; 64] = [
+    -65,  23,  16, -15, -56, -34,   2,  13,
+     29,  -1, -20,  -7,  -8,  -4, -38, -29,
+     -9,  24,   2, -16, -20,   6,  22, -22,
+    -17, -20, -12, -27, -30, -25, -14, -36,
+    -49,  -1, -27, -39, -46, -44, -33, -51,
+    -14, -14, -22, -46, -44, -30, -15, -27,
+      1,   7,  -8, -64, -43, -16,   9,   8,
+    -15,  36,  12, -54,   8, -28,  24,  14,
+];
+
+#[rustfmt::skip]
+const KING_EG: [i32; 64] = [
+    -74, -35, -18, -18, -11,  15,   4, -17,
+    -12,  17,  14,  17,  17,  38,  23,  11,
+     10,  17,  23,  15,  20,  45,  44,  13,
+     -8,  22,  24,  27,  26,  33,  26,   3,
+    -18,  -4,  21,  24,  27,  23,   9, -11,
+    -19,  -3,  11,  21,  23,  16,   7,  -9,
+    -27, -11,   4,  13,  14,   4,  -5, -17,
+    -53, -34, -21, -11, -28, -14, -24, -43,
+];
+
+#[rustfmt::skip]
+const QUEEN_MG: [i32; 64] = [
+    -28,   0,  29,  12,  59,  44,  43,  45,
+    -24, -39,  -5,   1, -16,  57,  28,  54,
+    -13, -17,   7,   8,  29,  56,  47,  57,
+    -27, -27, -16, -16,  -1,  17,  -2,   1,
+     -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+    -14,   2, -11,  -2,  -5,   2,  14,   5,
+    -35,  -8,  11,   2,   8,  15,  -3,   1,
+     -1, -18,  -9,  10, -15, -25, -31, -50,
+];
+
+#[rustfmt::skip]
+const QUEEN_EG: [i32; 64] = [
+     -9,  22,  22,  27,  27,  19,  10,  20,
+    -17,  20,  32,  41,  58,  25,  30,   0,
+    -20,   6,   9,  49,  47,  35,  19,   9,
+      3,  22,  24,  45,  57,  40,  57,  36,
+    -18,  28,  19,  47,  31,  34,  39,  23,
+    -16, -27,  15,   6,   9,  17,  10,   5,
+    -22, -23, -30, -16, -16, -23, -36, -32,
+    -33, -28, -22, -43,  -5, -32, -20, -41,
+];
+
+#[rustfmt::skip]
+const ROOK_MG: [i32; 64] = [
+     32,  42,  32,  51,  63,   9,  31,  43,
+     27,  32,  58,  62,  80,  67,  26,  44,
+     -5,  19,  26,  36,  17,  45,  61,  16,
+    -24, -11,   7,  26,  24,  35,  -8, -20,
+    -36, -26, -12,  -1,   9,  -7,   6, -23,
+    -45, -25, -16, -17,   3,   0,  -5, -33,
+    -44, -16, -20,  -9,  -1,  11,  -6, -71,
+    -19, -13,   1,  17,  16,   7, -37, -26,
+];
+
+#[rustfmt::skip]
+const ROOK_EG: [i32; 64] = [
+     13,  10,  18,  15,  12,  12,   8,   5,
+     11,  13,  13,  11,  -3,   3,   8,   3,
+      7,   7,   7,   5,   4,  -3,  -5,  -3,
+      4,   3,  13,   1,   2,   1,  -1,   2,
+      3,   5,   8,   4,  -5,  -6,  -8, -11,
+     -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+     -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+     -9,   2,   3,  -1,  -5, -13,   4, -20,
+];
+
+#[rustfmt::skip]
+const BISHOP_MG: [i32; 64] = [
+    -29,   4, -82, -37, -25, -42,   7,  -8,
+    -26,  16, -18, -13,  30,  59,  18, -47,
+    -16,  37,  43,  40,  35,  50,  37,  -2,
+     -4,   5,  19,  50,  37,  37,   7,  -2,
+     -6,  13,  13,  26,  34,  12,  10,   4,
+      0,  15,  15,  15,  14,  27,  18,  10,
+      4,  15,  16,   0,   7,  21,  33,   1,
+    -33,  -3, -14, -21, -13, -12, -39, -21,
+];
+
+#[rustfmt::skip]
+const BISHOP_EG: [i32; 64] = [
+    -14, -21, -11,  -8,  -7,  -9, -17, -24,
+     -8,  -4,   7, -12,  -3, -13,  -4, -14,
+      2,  -8,   0,  -1,  -2,   6,   0,   4,
+     -3,   9,  12,   9,  14,  10,   3,   2,
+     -6,   3,  13,  19,   7,  10,  -3,  -9,
+    -12,  -3,   8,  10,  13,   3,  -7, -15,
+    -14, -18,  -7,  -1,   4,  -9, -15, -27,
+    -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+];
+
+#[rustfmt::skip]
+const KNIGHT_MG: [i32; 64] = [
+   -167, -89, -34, -49,  61, -97, -15, -107,
+    -73, -41,  72,  36,  23,  62,   7,  -17,
+    -47,  60,  37,  65,  84, 129,  73,   44,
+     -9,  17,  19,  53,  37,  69,  18,   22,
+    -13,   4,  16,  13,  28,  19,  21,   -8,
+    -23,  -9,  12,  10,  19,  17,  25,  -16,
+    -29, -53, -12,  -3,  -1,  18, -14,  -19,
+   -105, -21, -58, -33, -17, -28, -19,  -23,
+];
+
+#[rustfmt::skip]
+const KNIGHT_EG: [i32; 64] = [
+    -58, -38, -13, -28, -31, -27, -63, -99,
+    -25,  -8, -25,  -2,  -9, -25, -24, -52,
+    -24, -20,  10,   9,  -1,  -9, -19, -41,
+    -17,   3,  22,  22,  22,  11,   8, -18,
+    -18,  -6,  16,  25,  16,  17,   4, -18,
+    -23,  -3,  -1,  15,  10,  -3, -20, -22,
+    -42, -20, -10,  -5,  -2, -20, -23, -44,
+    -29, -51, -23, -15, -22, -18, -50, -64,
+];
+
+#[rustfmt::skip]
+const PAWN_MG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     98, 134,  61,  95,  68, 126,  34, -11,
+     -6,   7,  26,  31,  65,  56,  25, -20,
+    -14,  13,   6,  21,  23,  12,  17, -23,
+    -27,  -2,  -5,  12,  17,   6,  10, -25,
+    -26,  -4,  -4, -10,   3,   3,  33, -12,
+    -35,  -1, -20, -23, -15,  24,  38, -22,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const PAWN_EG: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+    178, 173, 158, 134, 147, 132, 165, 187,
+     94, 100,  85,  67,  56,  53,  82,  84,
+     32,  24,  13,   5,  -2,   4,  17,  17,
+     13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+      4,   7,  -6,   1,   0,  -5,  -1,  -8,
+     13,   8,   8,  10,  13,   0,   2,  -7,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+/// Per-term breakdown of a static evaluation.
+///
+/// Every term is tapered and given from white's point of view; `score` is the final value from
+/// the point of view of the side to move.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Evaluation {
+    pub material: i32,
+    pub piece_squares: i32,
+    pub mobility: i32,
+    pub king_safety: i32,
+    pub pawn_structure: i32,
+    pub phase: i32,
+    pub score: i32,
+}
+
+#[derive(Clone, Copy, Default)]
+struct TaperedScore {
+    middlegame: i32,
+    endgame: i32,
+}
+
+impl TaperedScore {
+    fn add(&mut self, (middlegame, endgame): (i32, i32), color: Color) {
+        let sign = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        self.middlegame += sign * middlegame;
+        self.endgame += sign * endgame;
+    }
+
+    fn taper(&self, phase: i32) -> i32 {
+        (self.middlegame * phase + self.endgame * (TOTAL_PHASE - phase)) / TOTAL_PHASE
+    }
+}
+
+pub fn evaluate(board: &Board) -> i32 {
+    evaluate_with_breakdown(board).score
+}
+
+pub fn evaluate_with_breakdown(board: &Board) -> Evaluation {
+    let phase = game_phase(board);
+    let mut material = TaperedScore::default();
+    let mut piece_squares = TaperedScore::default();
+    let mut mobility = TaperedScore::default();
+
+    for (row, pieces) in board.board.iter().enumerate() {
+        for (column, square) in pieces.iter().enumerate() {
+            let piece = match square {
+                Some(piece) => piece,
+                None => continue,
+            };
+            let index = piece_index(piece.kind);
+            material.add(MATERIAL[index], piece.color);
+            piece_squares.add(piece_square_value(piece, (row, column)), piece.color);
+            let (middlegame, endgame) = MOBILITY[index];
+            if middlegame != 0 || endgame != 0 {
+                let reachable =
+                    count_mobility(board, piece, (row, column)) - MOBILITY_BASELINE[index];
+                mobility.add((middlegame * reachable, endgame * reachable), piece.color);
+            }
+        }
+    }
+
+    let mut king_safety = TaperedScore::default();
+    let mut pawn_structure = TaperedScore::default();
+    for color in [Color::White, Color::Black] {
+        king_safety.add(king_safety_term(board, color), color);
+        pawn_structure.add(pawn_structure_term(board, color), color);
+    }
+
+    let mut evaluation = Evaluation {
+        material: material.taper(phase),
+        piece_squares: piece_squares.taper(phase),
+        mobility: mobility.taper(phase),
+        king_safety: king_safety.taper(phase),
+        pawn_structure: pawn_structure.taper(phase),
+        phase,
+        score: 0,
+    };
+    let white_score = match board.variant {
+        Variant::Antichess => -evaluation.material,
+        Variant::RacingKings => {
+            evaluation.material + racing_kings_progress(board) + evaluation.mobility
+        }
+        _ => {
+            evaluation.material
+                + evaluation.piece_squares
+                + evaluation.mobility
+                + evaluation.king_safety
+                + evaluation.pawn_structure
+        }
+    };
+    evaluation.score = match board.player_turn {
+        Color::White => white_score,
+        Color::Black => -white_score,
+    };
+    evaluation
+}
+
+/// Remaining non-pawn material, from 24 in the opening down to 0 in a bare pawn ending.
+pub fn game_phase(board: &Board) -> i32 {
+    let phase: i32 = board
+        .board
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|piece| PHASE_WEIGHTS[piece_index(piece.kind)])
+        .sum();
+    phase.min(TOTAL_PHASE)
+}
+
+fn piece_index(kind: PieceKind) -> usize {
+    match kind {
+        PieceKind::King => 0,
+        PieceKind::Queen => 1,
+        PieceKind::Rook => 2,
+        PieceKind::Bishop => 3,
+        PieceKind::Knight => 4,
+        PieceKind::Pawn => 5,
+    }
+}
+
+fn piece_square_value(piece: &Piece, (row, column): (usize, usize)) -> (i32, i32) {
+    let index = match piece.color {
+        Color::White => (7 - row) * 8 + column,
+        Color::Black => row * 8 + column,
+    };
+    let (middlegame, endgame) = match piece.kind {
+        PieceKind::King => (&KING_MG, &KING_EG),
+        PieceKind::Queen => (&QUEEN_MG, &QUEEN_EG),
+        PieceKind::Rook => (&ROOK_MG, &ROOK_EG),
+        PieceKind::Bishop => (&BISHOP_MG, &BISHOP_EG),
+        PieceKind::Knight => (&KNIGHT_MG, &KNIGHT_EG),
+        PieceKind::Pawn => (&PAWN_MG, &PAWN_EG),
+    };
+    (middlegame[index], endgame[index])
+}
+
+fn count_mobility(board: &Board, piece: &Piece, origin: (usize, usize)) -> i32 {
+    let moves = match piece.kind {
+        PieceKind::Queen => moves::queen_moves(board, &piece.color, origin),
+        PieceKind::Rook => moves::rook_moves(board, &piece.color, origin),
+        PieceKind::Bishop => moves::bishop_moves(board, &piece.color, origin),
+        PieceKind::Knight => moves::knight_moves(board, &piece.color, origin),
+        PieceKind::King | PieceKind::Pawn => return 0,
+    };
+    moves.len() as i32
+}
+
+fn king_safety_term(board: &Board, color: Color) -> (i32, i32) {
+    let king_square = match get_king_square(board.board, &color) {
+        Some(square) => square,
+        None => return (0, 0),
+    };
+    let forward: isize = match color {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+    let enemy = color.get_opposite_color();
+    let mut middlegame = 0;
+    for row_offset in -1..=1 {
+        for column_offset in -1..=1 {
+            let square = (
+                king_square.0 as isize + row_offset,
+                king_square.1 as isize + column_offset,
+            );
+            if !moves::is_square_within_bounds(square) {
+                continue;
+            }
+            let square = (square.0 as usize, square.1 as usize);
+            if is_square_attacked(board, square, &enemy) {
+                middlegame += KING_ZONE_ATTACK;
+            }
+            if row_offset == forward
+                && board.board[square.0][square.1] == Some(Piece::new(PieceKind::Pawn, color))
+            {
+                middlegame += PAWN_SHIELD;
+            }
+        }
+    }
+    (middlegame, 0)
+}
+
+fn pawn_structure_term(board: &Board, color: Color) -> (i32, i32) {
+    let mut pawns_per_file = [0; 8];
+    let mut pawns: Vec<(usize, usize)> = Vec::new();
+    for (row, pieces) in board.board.iter().enumerate() {
+        for (column, square) in pieces.iter().enumerate() {
+            if *square == Some(Piece::new(PieceKind::Pawn, color)) {
+                pawns_per_file[column] += 1;
+                pawns.push((row, column));
+            }
+        }
+    }
+
+    let mut score = (0, 0);
+    let mut add = |(middlegame, endgame): (i32, i32)| {
+        score.0 += middlegame;
+        score.1 += endgame;
+    };
+    for count in pawns_per_file {
+        if count > 1 {
+            add((DOUBLED_PAWN.0 * (count - 1), DOUBLED_PAWN.1 * (count - 1)));
+        }
+    }
+    for (row, column) in pawns {
+        let left = column.checked_sub(1).map_or(0, |file| pawns_per_file[file]);
+        let right = pawns_per_file.get(column + 1).copied().unwrap_or(0);
+        if left == 0 && right == 0 {
+            add(ISOLATED_PAWN);
+        }
+        if is_passed_pawn(board, color, (row, column)) {
+            let relative_rank = match color {
+                Color::White => row,
+                Color::Black => 7 - row,
+            };
+            add(PASSED_PAWN[relative_rank]);
+        }
+    }
+    score
+}
+
+fn is_passed_pawn(board: &Board, color: Color, (row, column): (usize, usize)) -> bool {
+    let enemy_pawn = Some(Piece::new(PieceKind::Pawn, color.get_opposite_color()));
+    let rows_ahead: Vec<usize> = match color {
+        Color::White => (row + 1..8).collect(),
+        Color::Black => (0..row).collect(),
+    };
+    let columns = column.saturating_sub(1)..=(column + 1).min(7);
+    !rows_ahead.iter().any(|ahead| {
+        columns
+            .clone()
+            .any(|file| board.board[*ahead][file] == enemy_pawn)
+    })
+}
+
+fn racing_kings_progress(board: &Board) -> i32 {
+    let rank =
+        |color: Color| get_king_square(board.board, &color).map_or(0, |square| square.0 as i32);
+    (rank(Color::White) - rank(Color::Black)) * 60
+}
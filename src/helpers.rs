@@ -5,36 +5,30 @@ pub fn get_attacked_squares(board: &Board, player_turn: Color) -> HashSet<(usize
     let mut attacked_squares: HashSet<(usize, usize)> = HashSet::new();
     for row in 0..8 {
         for column in 0..8 {
-            match board.board[row][column] {
-                Some(piece) => {
-                    if piece.color != player_turn {
-                        let moves: Vec<Move> = match piece.kind {
-                            PieceKind::King => {
-                                moves::king_moves(&board, &piece.color, (row, column))
-                            }
-                            PieceKind::Queen => {
-                                moves::queen_moves(&board, &piece.color, (row, column))
-                            }
-                            PieceKind::Rook => {
-                                moves::rook_moves(&board, &piece.color, (row, column))
-                            }
-                            PieceKind::Bishop => {
-                                moves::bishop_moves(&board, &piece.color, (row, column))
-                            }
-                            PieceKind::Knight => {
-                                moves::knight_moves(&board, &piece.color, (row, column))
-                            }
-                            PieceKind::Pawn => {
-                                moves::pawn_attacking_moves(&board, &piece.color, (row, column))
-                            }
-                        };
-                        moves.iter().for_each(|m| {
-                            attacked_squares.insert(m.piece_move.destination);
-                        });
+            let piece = match board.board[row][column] {
+                Some(piece) if piece.color != player_turn => piece,
+                _ => continue,
+            };
+            let moves: Vec<Move> = match piece.kind {
+                PieceKind::King => {
+                    if board.variant == Variant::Atomic {
+                        continue;
                     }
+                    moves::king_moves(board, &piece.color, (row, column))
                 }
-                None => {}
-            }
+                PieceKind::Queen => moves::queen_moves(board, &piece.color, (row, column)),
+                PieceKind::Rook => moves::rook_moves(board, &piece.color, (row, column)),
+                PieceKind::Bishop => moves::bishop_moves(board, &piece.color, (row, column)),
+                PieceKind::Knight => moves::knight_moves(board, &piece.color, (row, column)),
+                PieceKind::Pawn => {
+                    attacked_squares
+                        .extend(moves::pawn_attacked_squares(&piece.color, (row, column)));
+                    continue;
+                }
+            };
+            moves.iter().for_each(|m| {
+                attacked_squares.insert(m.piece_move.destination);
+            });
         }
     }
     attacked_squares
@@ -54,17 +48,14 @@ pub fn get_move_from_vec(move_vec: Vec<Move>, move_struct: &UserMove) -> Result<
 }
 
 pub fn parse_en_passant_fen(square: &str) -> Option<(usize, usize)> {
-    match square_to_tuple(square) {
-        Ok(tuple) => Some(tuple),
-        _ => None,
-    }
+    square_to_tuple(square).ok()
 }
 
 pub fn tuple_to_square(tuple: Option<(usize, usize)>) -> String {
     match tuple {
         Some(t) => {
             let mut square = String::new();
-            let row: char = char::from_u32(t.0 as u32 + 48).unwrap();
+            let row: char = char::from_u32(t.0 as u32 + 49).unwrap();
             let column = char::from_u32(t.1 as u32 + 97).unwrap();
             square.push(column);
             square.push(row);
@@ -98,6 +89,77 @@ pub fn square_to_tuple(square: &str) -> Result<(usize, usize), String> {
 
     Ok((row, column))
 }
+
+/// A from and a to square, such as the ends of an arrow.
+pub type SquarePair = ((usize, usize), (usize, usize));
+
+/// The two squares of a move or arrow written like `g1f3`.
+pub fn squares_to_tuples(squares: &str) -> Result<SquarePair, String> {
+    match squares.len() == 4 && squares.is_ascii() {
+        true => Ok((
+            square_to_tuple(&squares[..2])?,
+            square_to_tuple(&squares[2..])?,
+        )),
+        false => Err(format!("Invalid squares: {}", squares)),
+    }
+}
+
+/// The value following a command-line option.
+pub fn option_value<'a>(
+    arguments: &mut impl Iterator<Item = &'a String>,
+    name: &str,
+) -> Result<String, String> {
+    arguments
+        .next()
+        .cloned()
+        .ok_or(format!("Missing value for {}", name))
+}
+
+pub fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| format!("Invalid number: {}", text))
+}
+
+/// Checks every FEN field, returning the FEN with the move counters filled in when they were
+/// omitted, as some GUIs do.
+pub fn normalize_fen(fen: &str) -> Result<String, String> {
+    let mut fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() == 4 {
+        fields.extend(["0", "1"]);
+    }
+    if fields.len() != 6 {
+        return Err(format!("Invalid FEN: {}", fen));
+    }
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+    let ranks_are_valid = ranks.len() == 8
+        && ranks.iter().all(|rank| {
+            let mut squares = 0;
+            for c in rank.chars() {
+                match c {
+                    '1'..='8' => squares += c.to_digit(10).unwrap(),
+                    'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => {
+                        squares += 1
+                    }
+                    _ => return false,
+                }
+            }
+            squares == 8
+        });
+    let castling_is_valid = fields[2] == "-" || fields[2].chars().all(|c| "KQkq".contains(c));
+    let en_passant_is_valid =
+        fields[3] == "-" || square_to_tuple(fields[3]).is_ok() && fields[3].len() == 2;
+    if !ranks_are_valid
+        || !matches!(fields[1], "w" | "b")
+        || !castling_is_valid
+        || !en_passant_is_valid
+        || fields[4].parse::<usize>().is_err()
+        || fields[5].parse::<usize>().is_err()
+    {
+        return Err(format!("Invalid FEN: {}", fen));
+    }
+    Ok(fields.join(" "))
+}
+
 pub fn populate_board_from_fen(board: &mut [[Option<Piece>; 8]; 8], fen: &str) {
     let mut row = 7;
     let mut column = 0;
@@ -189,33 +251,179 @@ pub fn castle_rights_to_fen(castle_rights: &CastleRights) -> String {
     if castle_rights.black_queen_side {
         fen.push('q');
     }
-    if fen.len() == 0 {
+    if fen.is_empty() {
         fen.push('-');
         return fen;
     }
     fen
 }
 
-pub fn move_causes_check_on_self(mut board: Board, simulated_move: &Move) -> bool {
-    board.handle_move(simulated_move);
-    is_checked(&board)
+pub fn is_legal_move(mut board: Board, simulated_move: &Move) -> bool {
+    if board.variant == Variant::Antichess {
+        return true;
+    }
+    let mut simulated_move = simulated_move.clone();
+    if simulated_move.side_effects.contains(&SideEffect::Promotion)
+        && simulated_move.piece_move.promotion_request.is_none()
+    {
+        simulated_move.piece_move.promotion_request = Some(PieceKind::Queen);
+    }
+    board.handle_move(&simulated_move);
+    if board.variant == Variant::Atomic {
+        let enemy = board.player_turn.get_opposite_color();
+        if get_king_square(board.board, &board.player_turn).is_none() {
+            return false;
+        }
+        if get_king_square(board.board, &enemy).is_none() {
+            return true;
+        }
+    }
+    if board.variant == Variant::RacingKings
+        && is_king_attacked(&board, &board.player_turn.get_opposite_color())
+    {
+        return false;
+    }
+    !is_checked(&board)
 }
 
 pub fn is_checked(board: &Board) -> bool {
-    let attacked_squares = get_attacked_squares(&board, board.player_turn);
-    let king_square = get_king_square(board.board, &board.player_turn);
-    attacked_squares.contains(&king_square)
+    is_king_attacked(board, &board.player_turn)
 }
 
-pub fn get_king_square(board: [[Option<Piece>; 8]; 8], color: &Color) -> (usize, usize) {
-    for row in 0..8 {
-        for column in 0..8 {
-            if let Some(piece) = board[row][column] {
+pub fn is_king_attacked(board: &Board, color: &Color) -> bool {
+    if board.variant == Variant::Antichess {
+        return false;
+    }
+    let king_square = match get_king_square(board.board, color) {
+        Some(square) => square,
+        None => return false,
+    };
+    if board.variant == Variant::Atomic && kings_are_adjacent(board.board) {
+        return false;
+    }
+    is_square_attacked(board, king_square, &color.get_opposite_color())
+}
+
+pub fn is_square_attacked(board: &Board, square: (usize, usize), attacker: &Color) -> bool {
+    let is_attacker = |offset: (isize, isize), kinds: &[PieceKind]| {
+        let target = (square.0 as isize + offset.0, square.1 as isize + offset.1);
+        if !moves::is_square_within_bounds(target) {
+            return false;
+        }
+        match board.board[target.0 as usize][target.1 as usize] {
+            Some(piece) => piece.color == *attacker && kinds.contains(&piece.kind),
+            None => false,
+        }
+    };
+    let pawn_row_offset = match attacker {
+        Color::White => -1,
+        Color::Black => 1,
+    };
+    if is_attacker((pawn_row_offset, -1), &[PieceKind::Pawn])
+        || is_attacker((pawn_row_offset, 1), &[PieceKind::Pawn])
+    {
+        return true;
+    }
+    let knight_offsets = [
+        (2, 1),
+        (2, -1),
+        (-2, 1),
+        (-2, -1),
+        (1, 2),
+        (1, -2),
+        (-1, 2),
+        (-1, -2),
+    ];
+    if knight_offsets
+        .iter()
+        .any(|offset| is_attacker(*offset, &[PieceKind::Knight]))
+    {
+        return true;
+    }
+    let king_offsets = [
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+        (1, 0),
+        (1, -1),
+        (0, -1),
+    ];
+    if board.variant != Variant::Atomic
+        && king_offsets
+            .iter()
+            .any(|offset| is_attacker(*offset, &[PieceKind::King]))
+    {
+        return true;
+    }
+    for (index, direction) in king_offsets.iter().enumerate() {
+        let slider = if index % 2 == 0 {
+            PieceKind::Bishop
+        } else {
+            PieceKind::Rook
+        };
+        let mut target = (square.0 as isize, square.1 as isize);
+        loop {
+            target = (target.0 + direction.0, target.1 + direction.1);
+            if !moves::is_square_within_bounds(target) {
+                break;
+            }
+            if let Some(piece) = board.board[target.0 as usize][target.1 as usize] {
+                if piece.color == *attacker
+                    && (piece.kind == slider || piece.kind == PieceKind::Queen)
+                {
+                    return true;
+                }
+                break;
+            }
+        }
+    }
+    false
+}
+
+pub fn kings_are_adjacent(board: [[Option<Piece>; 8]; 8]) -> bool {
+    match (
+        get_king_square(board, &Color::White),
+        get_king_square(board, &Color::Black),
+    ) {
+        (Some(white), Some(black)) => {
+            white.0.abs_diff(black.0) <= 1 && white.1.abs_diff(black.1) <= 1
+        }
+        _ => false,
+    }
+}
+
+pub fn get_king_square(board: [[Option<Piece>; 8]; 8], color: &Color) -> Option<(usize, usize)> {
+    for (row, pieces) in board.iter().enumerate() {
+        for (column, square) in pieces.iter().enumerate() {
+            if let Some(piece) = square {
                 if piece.kind == PieceKind::King && piece.color == *color {
-                    return (row, column);
+                    return Some((row, column));
                 }
             }
         }
     }
-    (0, 0)
+    None
+}
+
+pub fn is_capture(m: &Move) -> bool {
+    m.side_effects.iter().any(|side_effect| {
+        matches!(
+            side_effect,
+            SideEffect::PieceTaken(_) | SideEffect::EnPassantTake(_)
+        )
+    })
+}
+
+pub fn is_tactical(m: &Move) -> bool {
+    is_capture(m) || m.side_effects.contains(&SideEffect::Promotion)
+}
+
+pub fn is_king_move(side_effect: &SideEffect) -> bool {
+    matches!(side_effect, SideEffect::KingMove(_))
+}
+
+pub fn is_castle(side_effect: &SideEffect) -> bool {
+    matches!(side_effect, SideEffect::Castle(..))
 }
@@ -0,0 +1,310 @@
+use crate::{helpers::tuple_to_square, models::*, pgn::PgnGame, Board};
+
+/// Annotation glyph asking for a diagram after the move, as written by ChessBase and others.
+const DIAGRAM_NAG: u8 = 220;
+/// A diagram seen from black's side.
+const FLIPPED_DIAGRAM_NAG: u8 = 221;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LatexPackage {
+    /// `\fenboard` and `\showboard`. Marked squares and arrows are not supported.
+    Skak,
+    /// `\newchessgame` and `\chessboard`, the extended `skak`.
+    Xskak,
+    /// `\chessboard` alone. The package has no move parser, so games are set as plain text.
+    Chessboard,
+}
+
+impl LatexPackage {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "skak" => Ok(LatexPackage::Skak),
+            "xskak" => Ok(LatexPackage::Xskak),
+            "chessboard" => Ok(LatexPackage::Chessboard),
+            _ => Err(format!("Unknown LaTeX package: {}", text)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LatexPackage::Skak => "skak",
+            LatexPackage::Xskak => "xskak",
+            LatexPackage::Chessboard => "chessboard",
+        }
+    }
+}
+
+/// How to set a board in LaTeX. Squares are `(row, column)` like `UserMove`.
+#[derive(Clone, Debug)]
+pub struct LatexOptions {
+    pub package: LatexPackage,
+    /// The side shown at the bottom.
+    pub orientation: Color,
+    /// File letters and rank numbers around the board.
+    pub coordinates: bool,
+    /// Squares framed on the board.
+    pub marked: Vec<(usize, usize)>,
+    /// Arrows from one square to another.
+    pub arrows: Vec<((usize, usize), (usize, usize))>,
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        LatexOptions {
+            package: LatexPackage::Xskak,
+            orientation: Color::White,
+            coordinates: true,
+            marked: Vec::new(),
+            arrows: Vec::new(),
+        }
+    }
+}
+
+/// The commands that set `board` as a diagram, for pasting into a document that loads the
+/// package.
+pub fn to_latex(board: &Board, options: &LatexOptions) -> String {
+    let fen = board.to_fen();
+    match options.package {
+        LatexPackage::Xskak => format!(
+            "\\newchessgame[setfen={}]\n\\chessboard{}\n",
+            fen,
+            chessboard_keys(None, options)
+        ),
+        _ => diagram(&fen, options),
+    }
+}
+
+/// `\usepackage` line for the package.
+pub fn preamble(package: LatexPackage) -> String {
+    format!("\\usepackage{{{}}}\n", package.name())
+}
+
+/// A diagram of the position with the given FEN, which leaves any game in progress alone.
+fn diagram(fen: &str, options: &LatexOptions) -> String {
+    match options.package {
+        LatexPackage::Skak => {
+            let show = match options.orientation {
+                Color::White => "\\showboard",
+                Color::Black => "\\showinverseboard",
+            };
+            match options.coordinates {
+                true => format!("\\fenboard{{{}}}\n{}\n", fen, show),
+                false => format!(
+                    "\\fenboard{{{}}}\n\\notationOff\n{}\n\\notationOn\n",
+                    fen, show
+                ),
+            }
+        }
+        LatexPackage::Xskak | LatexPackage::Chessboard => {
+            format!("\\chessboard{}\n", chessboard_keys(Some(fen), options))
+        }
+    }
+}
+
+fn chessboard_keys(fen: Option<&str>, options: &LatexOptions) -> String {
+    let mut keys: Vec<String> = Vec::new();
+    if let Some(fen) = fen {
+        keys.push(format!("setfen={}", fen));
+    }
+    if options.orientation == Color::Black {
+        keys.push("inverse".to_owned());
+    }
+    if !options.coordinates {
+        keys.push("label=false".to_owned());
+    }
+    if !options.marked.is_empty() {
+        let squares: Vec<String> = options
+            .marked
+            .iter()
+            .map(|square| tuple_to_square(Some(*square)))
+            .collect();
+        keys.push("pgfstyle=border".to_owned());
+        keys.push(format!("markfields={{{}}}", squares.join(",")));
+    }
+    if !options.arrows.is_empty() {
+        let moves: Vec<String> = options
+            .arrows
+            .iter()
+            .map(|(from, to)| {
+                format!(
+                    "{}-{}",
+                    tuple_to_square(Some(*from)),
+                    tuple_to_square(Some(*to))
+                )
+            })
+            .collect();
+        keys.push("pgfstyle=straightmove".to_owned());
+        keys.push("arrow=to".to_owned());
+        keys.push(format!("markmoves={{{}}}", moves.join(",")));
+    }
+    match keys.is_empty() {
+        true => String::new(),
+        false => format!("[{}]", keys.join(", ")),
+    }
+}
+
+enum Part {
+    Moves(Vec<String>),
+    Comment(String),
+    Diagram(String, Color),
+}
+
+/// A complete LaTeX document for a game: a heading from the tags, the main line with annotation
+/// glyphs and comments, and a diagram after each ply in `diagram_plies` (0 is the start position)
+/// and each move annotated with `$220`, or `$221` for a diagram from black's side.
+///
+/// `skak` and `xskak` typeset standard chess moves with `\mainline`; other variants, and every
+/// game with `chessboard`, are set as plain text.
+pub fn game_to_latex(
+    game: &PgnGame,
+    options: &LatexOptions,
+    diagram_plies: &[usize],
+) -> Result<String, String> {
+    let start = game.start_board()?;
+    let mainline =
+        options.package != LatexPackage::Chessboard && start.variant == Variant::Standard;
+    let flipped = options.orientation.get_opposite_color();
+
+    let mut parts: Vec<Part> = Vec::new();
+    if diagram_plies.contains(&0) {
+        parts.push(Part::Diagram(start.to_fen(), options.orientation));
+    }
+    let mut board = start;
+    let mut words: Vec<String> = Vec::new();
+    let mut needs_number = true;
+    for (index, pgn_move) in game.moves.iter().enumerate() {
+        match board.player_turn {
+            Color::White => words.push(format!("{}.", board.num_moves)),
+            Color::Black if needs_number => words.push(format!("{}...", board.num_moves)),
+            Color::Black => {}
+        }
+        needs_number = false;
+        let glyphs: String = pgn_move.nags.iter().filter_map(|nag| glyph(*nag)).collect();
+        words.push(format!("{}{}", pgn_move.san, glyphs));
+        board.make_move(&pgn_move.user_move);
+
+        let orientation = match (
+            pgn_move.nags.contains(&FLIPPED_DIAGRAM_NAG),
+            pgn_move.nags.contains(&DIAGRAM_NAG) || diagram_plies.contains(&(index + 1)),
+        ) {
+            (true, _) => Some(flipped),
+            (false, true) => Some(options.orientation),
+            (false, false) => None,
+        };
+        if pgn_move.comment.is_some() || orientation.is_some() {
+            parts.push(Part::Moves(std::mem::take(&mut words)));
+            needs_number = true;
+        }
+        if let Some(comment) = &pgn_move.comment {
+            parts.push(Part::Comment(comment.trim().to_owned()));
+        }
+        if let Some(orientation) = orientation {
+            parts.push(Part::Diagram(board.to_fen(), orientation));
+        }
+    }
+    parts.push(Part::Moves(words));
+
+    let mut text = String::from("\\documentclass{article}\n");
+    text.push_str(&preamble(options.package));
+    text.push_str("\\begin{document}\n\n");
+    text.push_str(&heading(game));
+    if mainline {
+        let fen = start.to_fen();
+        let standard = fen == start.variant.starting_fen();
+        text.push_str(&match (options.package, standard) {
+            (LatexPackage::Skak, true) => "\\newgame\n".to_owned(),
+            (LatexPackage::Skak, false) => format!("\\fenboard{{{}}}\n", fen),
+            (_, true) => "\\newchessgame\n".to_owned(),
+            (_, false) => format!("\\newchessgame[setfen={}]\n", fen),
+        });
+    }
+    text.push_str("\\noindent\n");
+    let diagram_options = LatexOptions {
+        marked: Vec::new(),
+        arrows: Vec::new(),
+        ..options.clone()
+    };
+    for part in parts {
+        match part {
+            Part::Moves(words) if words.is_empty() => {}
+            Part::Moves(words) => match mainline {
+                true => text.push_str(&format!("\\mainline{{{}}}\n", words.join(" "))),
+                false => text.push_str(&format!("{}\n", escape(&words.join(" ")))),
+            },
+            Part::Comment(comment) => text.push_str(&format!("{}\n", escape(&comment))),
+            Part::Diagram(fen, orientation) => {
+                let options = LatexOptions {
+                    orientation,
+                    ..diagram_options.clone()
+                };
+                text.push_str("\n\\begin{center}\n");
+                text.push_str(&diagram(&fen, &options));
+                text.push_str("\\end{center}\n\n\\noindent\n");
+            }
+        }
+    }
+    text.push_str(&format!("\\textbf{{{}}}\n", game.result.to_pgn()));
+    text.push_str("\n\\end{document}\n");
+    Ok(text)
+}
+
+/// The players in bold, with the event, site, round and date underneath when they are known.
+fn heading(game: &PgnGame) -> String {
+    let known = |name: &str| {
+        game.tag(name)
+            .filter(|value| !value.is_empty() && *value != "?")
+    };
+    let players = format!(
+        "{} -- {}",
+        escape(known("White").unwrap_or("?")),
+        escape(known("Black").unwrap_or("?"))
+    );
+    let details: Vec<String> = ["Event", "Site", "Round", "Date"]
+        .iter()
+        .filter_map(|name| known(name))
+        .filter(|value| !value.contains('?'))
+        .map(escape)
+        .collect();
+    match details.is_empty() {
+        true => format!(
+            "\\begin{{center}}\n\\textbf{{{}}}\n\\end{{center}}\n\n",
+            players
+        ),
+        false => format!(
+            "\\begin{{center}}\n\\textbf{{{}}}\\\\\n{}\n\\end{{center}}\n\n",
+            players,
+            details.join(", ")
+        ),
+    }
+}
+
+/// The move suffix for the six traditional annotation glyphs.
+fn glyph(nag: u8) -> Option<&'static str> {
+    match nag {
+        1 => Some("!"),
+        2 => Some("?"),
+        3 => Some("!!"),
+        4 => Some("??"),
+        5 => Some("!?"),
+        6 => Some("?!"),
+        _ => None,
+    }
+}
+
+/// Escapes the characters LaTeX treats specially in running text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,11 +1,35 @@
+pub mod endgame;
+pub mod engine;
+pub mod eval;
 pub mod helpers;
+pub mod latex;
 pub mod models;
+pub mod move_picker;
 pub mod moves;
+pub mod pgn;
+pub mod player;
+pub mod polyglot;
+pub mod problem;
+pub mod render;
+pub mod rng;
+pub mod san;
+pub mod search;
+pub mod see;
+pub mod skill;
+pub mod svg;
+pub mod terminal;
+pub mod timeman;
+pub mod tournament;
+pub mod tt;
+pub mod uci;
+pub mod xboard;
+pub mod zobrist;
 
+use eval::Evaluation;
 use helpers::*;
 use models::*;
+use search::{SearchLimits, SearchResult, Searcher};
 #[derive(Copy, Clone)]
-
 pub struct Board {
     pub board: [[Option<Piece>; 8]; 8],
     pub player_turn: Color,
@@ -13,6 +37,7 @@ pub struct Board {
     pub en_passant_square: Option<(usize, usize)>,
     pub num_half_moves: usize,
     pub num_moves: usize,
+    pub variant: Variant,
 }
 
 impl Board {
@@ -23,6 +48,10 @@ impl Board {
     }
 
     pub fn from_fen(fen: &str) -> Self {
+        Self::from_fen_with_variant(fen, Variant::Standard)
+    }
+
+    pub fn from_fen_with_variant(fen: &str, variant: Variant) -> Self {
         let fen: Vec<_> = fen.split(' ').collect();
 
         let mut board = Self::generate_empty_board();
@@ -46,9 +75,20 @@ impl Board {
             en_passant_square,
             num_half_moves,
             num_moves,
+            variant,
         }
     }
 
+    /// Like `from_fen_with_variant`, but reports a malformed FEN instead of panicking.
+    pub fn try_from_fen_with_variant(fen: &str, variant: Variant) -> Result<Self, String> {
+        let fen = normalize_fen(fen)?;
+        Ok(Self::from_fen_with_variant(&fen, variant))
+    }
+
+    pub fn from_variant(variant: Variant) -> Self {
+        Self::from_fen_with_variant(variant.starting_fen(), variant)
+    }
+
     pub fn to_fen(&self) -> String {
         let mut fen = String::new();
         let board = board_to_fen(self.board);
@@ -72,120 +112,326 @@ impl Board {
         let mut moves = Vec::new();
         for row in 0..8 {
             for column in 0..8 {
-                match &self.board[row][column] {
-                    Some(piece) => {
-                        if piece.color == self.player_turn {
-                            match piece.kind {
-                                PieceKind::King => {
-                                    moves.append(&mut moves::king_moves(
-                                        &self,
-                                        &self.player_turn,
-                                        (row, column),
-                                    ));
-                                    moves.append(&mut moves::castle_moves(
-                                        &self,
-                                        &self.player_turn,
-                                        (row, column),
-                                    ))
-                                }
-                                PieceKind::Queen => moves.append(&mut moves::queen_moves(
-                                    &self,
-                                    &self.player_turn,
-                                    (row, column),
-                                )),
-                                PieceKind::Rook => moves.append(&mut moves::rook_moves(
-                                    &self,
-                                    &self.player_turn,
-                                    (row, column),
-                                )),
-                                PieceKind::Bishop => moves.append(&mut moves::bishop_moves(
-                                    &self,
-                                    &self.player_turn,
-                                    (row, column),
-                                )),
-                                PieceKind::Knight => moves.append(&mut moves::knight_moves(
-                                    &self,
-                                    &piece.color,
-                                    (row, column),
-                                )),
-                                PieceKind::Pawn => {
-                                    moves.append(&mut moves::pawn_moves(
-                                        &self,
-                                        &piece.color,
-                                        (row, column),
-                                    ));
-                                    moves.append(&mut moves::pawn_attacking_moves(
-                                        &self,
-                                        &piece.color,
-                                        (row, column),
-                                    ));
-                                }
-                            }
-                        }
-                    }
-                    None => {}
-                }
+                moves.append(&mut self.get_moves_from_square((row, column)));
+            }
+        }
+        self.apply_variant_rules(moves)
+    }
+
+    fn get_moves_from_square(&self, (row, column): (usize, usize)) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let piece = match &self.board[row][column] {
+            Some(piece) if piece.color == self.player_turn => piece,
+            _ => return moves,
+        };
+        match piece.kind {
+            PieceKind::King => {
+                moves.append(&mut moves::king_moves(
+                    self,
+                    &self.player_turn,
+                    (row, column),
+                ));
+                moves.append(&mut moves::castle_moves(
+                    self,
+                    &self.player_turn,
+                    (row, column),
+                ))
+            }
+            PieceKind::Queen => moves.append(&mut moves::queen_moves(
+                self,
+                &self.player_turn,
+                (row, column),
+            )),
+            PieceKind::Rook => moves.append(&mut moves::rook_moves(
+                self,
+                &self.player_turn,
+                (row, column),
+            )),
+            PieceKind::Bishop => moves.append(&mut moves::bishop_moves(
+                self,
+                &self.player_turn,
+                (row, column),
+            )),
+            PieceKind::Knight => {
+                moves.append(&mut moves::knight_moves(self, &piece.color, (row, column)))
+            }
+            PieceKind::Pawn => {
+                moves.append(&mut moves::pawn_moves(self, &piece.color, (row, column)));
+                moves.append(&mut moves::pawn_attacking_moves(
+                    self,
+                    &piece.color,
+                    (row, column),
+                ));
             }
         }
         moves
     }
 
+    fn apply_variant_rules(&self, moves: Vec<Move>) -> Vec<Move> {
+        match self.variant {
+            Variant::Standard => moves,
+            Variant::Atomic => moves
+                .into_iter()
+                .filter(|m| !(is_capture(m) && m.side_effects.iter().any(is_king_move)))
+                .map(|mut m| {
+                    if is_capture(&m) {
+                        m.side_effects
+                            .push(SideEffect::Explosion(m.piece_move.destination));
+                    }
+                    m
+                })
+                .collect(),
+            Variant::Antichess | Variant::RacingKings => moves
+                .into_iter()
+                .filter(|m| !m.side_effects.iter().any(is_castle))
+                .collect(),
+            Variant::Horde => moves,
+        }
+    }
+
     pub fn make_move(&mut self, piece_move: &UserMove) -> MoveOutcome {
-        let moves = Self::get_valid_moves(&self, Self::get_all_moves_list(&self));
+        let moves = self.get_valid_moves(self.get_all_moves_list());
         match get_move_from_vec(moves, piece_move) {
             Ok(mut m) => {
                 m.piece_move = *piece_move;
-                match Self::handle_move(self, &m) {
-                    MoveOutcome::Error(e) => return MoveOutcome::Error(e),
-                    MoveOutcome::GameIsOver(e) => return MoveOutcome::GameIsOver(e),
-                    MoveOutcome::Success => {
-                        if self.player_turn == Color::Black {
-                            self.num_moves += 1;
-                        }
-                        self.player_turn = self.player_turn.get_opposite_color()
-                    }
-                }
+                self.apply_move(&m)
             }
-            Err(outcome) => {
-                return outcome;
+            Err(outcome) => outcome,
+        }
+    }
+
+    /// Passes the turn without moving, as used by null-move pruning.
+    fn apply_null_move(&mut self) {
+        self.en_passant_square = None;
+        self.num_half_moves += 1;
+        self.player_turn = self.player_turn.get_opposite_color();
+    }
+
+    fn apply_move(&mut self, m: &Move) -> MoveOutcome {
+        match self.handle_move(m) {
+            MoveOutcome::Error(e) => return MoveOutcome::Error(e),
+            MoveOutcome::GameIsOver(e) => return MoveOutcome::GameIsOver(e),
+            MoveOutcome::Success => {
+                if self.player_turn == Color::Black {
+                    self.num_moves += 1;
+                }
+                self.player_turn = self.player_turn.get_opposite_color()
             }
         }
         MoveOutcome::Success
     }
 
     pub fn game_status(&self) -> GameStatus {
-        let moves = Self::get_valid_moves(&self, Self::get_all_moves_list(&self));
-        if moves.len() == 0 {
+        self.game_status_with_moves(&self.get_valid_moves(self.get_all_moves_list()))
+    }
+
+    fn game_status_with_moves(&self, moves: &[Move]) -> GameStatus {
+        if self.variant == Variant::Atomic
+            && get_king_square(self.board, &self.player_turn).is_none()
+        {
+            return GameStatus::VariantWin(self.player_turn.get_opposite_color());
+        }
+        if self.variant == Variant::RacingKings {
+            if let Some(status) = self.racing_kings_status(moves) {
+                return status;
+            }
+        }
+        if moves.is_empty() && self.variant == Variant::Horde && !self.has_pieces(Color::White) {
+            return GameStatus::VariantWin(Color::Black);
+        }
+        if moves.is_empty() && self.variant == Variant::Antichess {
+            return GameStatus::VariantWin(self.player_turn);
+        }
+        if moves.is_empty() {
             if is_checked(self) {
-                return GameStatus::Checkmate(Color::get_opposite_color(&self.player_turn));
+                GameStatus::Checkmate(Color::get_opposite_color(&self.player_turn))
             } else {
-                return GameStatus::Draw;
+                GameStatus::Draw
             }
         } else if self.num_half_moves >= 100 {
-            return GameStatus::Draw;
+            GameStatus::Draw
         } else {
-            return GameStatus::Ongoing;
+            GameStatus::Ongoing
         }
     }
 
+    fn racing_kings_status(&self, moves: &[Move]) -> Option<GameStatus> {
+        let reached_goal =
+            |color: Color| get_king_square(self.board, &color).is_some_and(|square| square.0 == 7);
+        match (reached_goal(Color::White), reached_goal(Color::Black)) {
+            (true, true) => Some(GameStatus::Draw),
+            (false, true) => Some(GameStatus::VariantWin(Color::Black)),
+            (true, false) => {
+                let black_can_equalise = self.player_turn == Color::Black
+                    && moves.iter().any(|m| {
+                        m.side_effects.iter().any(is_king_move) && m.piece_move.destination.0 == 7
+                    });
+                if black_can_equalise {
+                    None
+                } else {
+                    Some(GameStatus::VariantWin(Color::White))
+                }
+            }
+            (false, false) => None,
+        }
+    }
+
+    fn has_pieces(&self, color: Color) -> bool {
+        self.board
+            .iter()
+            .flatten()
+            .any(|square| square.is_some_and(|piece| piece.color == color))
+    }
+
     fn copy_board(&self) -> Self {
-        self.clone()
+        *self
     }
 
     fn get_valid_moves(&self, moves: Vec<Move>) -> Vec<Move> {
         let mut valid_moves: Vec<Move> = Vec::new();
-        let board = Self::copy_board(&self);
+        let board = self.copy_board();
         for m in moves {
-            if !move_causes_check_on_self(board, &m) {
+            if is_legal_move(board, &m) {
                 valid_moves.push(m);
             }
         }
+        if self.variant == Variant::Antichess && valid_moves.iter().any(is_capture) {
+            valid_moves.retain(is_capture);
+        }
         valid_moves
     }
 
+    fn legal_moves(&self) -> Vec<Move> {
+        self.expand_promotions(self.get_valid_moves(self.get_all_moves_list()))
+    }
+
+    /// Legal moves of the piece on `origin`. Only exact for variants where a move's legality does
+    /// not depend on the other pieces' moves, i.e. everything but Antichess.
+    fn legal_moves_from_square(&self, origin: (usize, usize)) -> Vec<Move> {
+        let moves = self.apply_variant_rules(self.get_moves_from_square(origin));
+        self.expand_promotions(self.get_valid_moves(moves))
+    }
+
+    fn expand_promotions(&self, valid_moves: Vec<Move>) -> Vec<Move> {
+        let promotion_kinds: &[PieceKind] = match self.variant {
+            Variant::Antichess => &[
+                PieceKind::Queen,
+                PieceKind::Rook,
+                PieceKind::Bishop,
+                PieceKind::Knight,
+                PieceKind::King,
+            ],
+            _ => &[
+                PieceKind::Queen,
+                PieceKind::Rook,
+                PieceKind::Bishop,
+                PieceKind::Knight,
+            ],
+        };
+        let mut moves: Vec<Move> = Vec::new();
+        for m in valid_moves {
+            if m.side_effects.contains(&SideEffect::Promotion) {
+                for kind in promotion_kinds {
+                    let mut promotion = m.clone();
+                    promotion.piece_move.promotion_request = Some(*kind);
+                    moves.push(promotion);
+                }
+            } else {
+                moves.push(m);
+            }
+        }
+        moves
+    }
+
+    /// Zobrist key of the position, covering pieces, side to move, castle rights and en passant.
+    pub fn hash(&self) -> u64 {
+        zobrist::hash(self)
+    }
+
+    pub fn evaluate(&self) -> i32 {
+        eval::evaluate(self)
+    }
+
+    pub fn evaluation(&self) -> Evaluation {
+        eval::evaluate_with_breakdown(self)
+    }
+
+    /// Static exchange evaluation of `piece_move` in centipawns; negative values flag captures
+    /// that lose material once all recaptures on the square are played out.
+    pub fn see(&self, piece_move: &UserMove) -> i32 {
+        see::static_exchange(self, piece_move)
+    }
+
+    pub fn best_move(&self, limits: SearchLimits) -> SearchResult {
+        Searcher::new(limits).search(self)
+    }
+
+    /// A move played at a reduced strength. Seeding `rng` with `Rng::new` makes the choice
+    /// reproducible.
+    pub fn best_move_with_skill(
+        &self,
+        limits: SearchLimits,
+        skill: skill::Skill,
+        rng: &mut rng::Rng,
+    ) -> SearchResult {
+        skill.choose(self.best_move(skill.limits(limits)), rng)
+    }
+
+    /// Legal captures and promotions only, as searched by quiescence.
+    fn tactical_moves(&self) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+        moves.retain(is_tactical);
+        moves
+    }
+
+    /// The move in standard algebraic notation, e.g. `Nf3`, `exd5` or `O-O`.
+    pub fn to_san(&self, piece_move: &UserMove) -> Result<String, String> {
+        san::to_san(self, piece_move)
+    }
+
+    pub fn parse_san(&self, text: &str) -> Result<UserMove, String> {
+        san::from_san(self, text)
+    }
+
+    /// Perfect-play result of a position with at most four pieces, if `tablebase` holds its
+    /// ending.
+    pub fn probe_endgame(&self, tablebase: &endgame::Tablebase) -> Option<endgame::EndgameProbe> {
+        tablebase.probe(self)
+    }
+
+    /// Solves a chess problem set in this position, listing every key move (more than one means
+    /// the problem is cooked) and, for mates and selfmates, the continuations after each defence
+    /// (more than one means a dual).
+    pub fn solve_problem(
+        &self,
+        stipulation: problem::Stipulation,
+    ) -> Result<problem::ProblemReport, String> {
+        problem::solve(self, stipulation)
+    }
+
+    /// Draws the board as text, for terminals and logs. `Display` gives the default drawing.
+    pub fn render(&self, options: &render::RenderOptions) -> String {
+        render::render(self, options)
+    }
+
+    /// A framed text diagram in the style of printed books, from `orientation`'s side.
+    pub fn to_book_diagram(&self, orientation: Color) -> String {
+        render::book_diagram(self, orientation)
+    }
+
+    /// A self-contained SVG diagram of the board, for web pages and print.
+    pub fn to_svg(&self, options: &svg::SvgOptions) -> String {
+        svg::to_svg(self, options)
+    }
+
+    /// The board in LaTeX for the `skak`, `xskak` or `chessboard` package.
+    pub fn to_latex(&self, options: &latex::LatexOptions) -> String {
+        latex::to_latex(self, options)
+    }
+
     pub fn valid_moves(&self) -> Vec<ValidMove> {
         let mut valid_move_vec: Vec<ValidMove> = vec![];
-        let moves = self.get_valid_moves(Self::get_all_moves_list(&self));
+        let moves = self.get_valid_moves(self.get_all_moves_list());
 
         for m in moves {
             valid_move_vec.push(ValidMove {
@@ -201,6 +447,7 @@ impl Board {
         let mut need_to_reset_half_moves = false;
         let mut double_pawn_move_made = false;
         let mut piece_promoted = false;
+        let mut explosion_square: Option<(usize, usize)> = None;
         for side_effect in &move_struct.side_effects {
             match side_effect {
                 SideEffect::EnPassantTake(enemy_pawn_coords) => {
@@ -212,7 +459,7 @@ impl Board {
                     double_pawn_move_made = true;
                 }
                 SideEffect::Castle(rook_origin, rook_destination, color) => {
-                    Self::move_piece(self, *rook_origin, *rook_destination);
+                    self.move_piece(*rook_origin, *rook_destination);
                     match color {
                         Color::White => {
                             self.castle_rights.white_king_side = false;
@@ -241,22 +488,24 @@ impl Board {
                     RookType::BlackQueenSide => self.castle_rights.black_queen_side = false,
                 },
                 SideEffect::PawnMove => need_to_reset_half_moves = true,
-                SideEffect::PieceTaken(_) => need_to_reset_half_moves = true,
+                SideEffect::PieceTaken(_) => {
+                    self.revoke_castle_rights_at(move_struct.piece_move.destination);
+                    need_to_reset_half_moves = true;
+                }
+                SideEffect::Explosion(square) => explosion_square = Some(*square),
                 SideEffect::Promotion => match move_struct.piece_move.promotion_request {
                     Some(promotion_piecekind) => match promotion_piecekind {
+                        PieceKind::King if self.variant == Variant::Antichess => {
+                            self.promote(move_struct, PieceKind::King);
+                            piece_promoted = true;
+                        }
                         PieceKind::King | PieceKind::Pawn => {
                             return MoveOutcome::Error(MoveError::InvalidPromotion(
                                 promotion_piecekind,
                             ));
                         }
                         valid_piece => {
-                            let (origin_row, origin_column) = move_struct.piece_move.origin;
-                            let piece_color = self.board[origin_row][origin_column].unwrap().color;
-                            let promoted_piece = Piece::new(valid_piece, piece_color);
-                            let (destination_row, destination_column) =
-                                move_struct.piece_move.destination;
-                            self.board[origin_row][origin_column] = None;
-                            self.board[destination_row][destination_column] = Some(promoted_piece);
+                            self.promote(move_struct, valid_piece);
                             piece_promoted = true;
                         }
                     },
@@ -267,12 +516,14 @@ impl Board {
             }
         }
         if !piece_promoted {
-            Self::move_piece(
-                self,
+            self.move_piece(
                 move_struct.piece_move.origin,
                 move_struct.piece_move.destination,
             );
         }
+        if let Some(square) = explosion_square {
+            self.explode(square);
+        }
 
         if need_to_reset_half_moves {
             self.num_half_moves = 0;
@@ -285,6 +536,48 @@ impl Board {
         MoveOutcome::Success
     }
 
+    fn promote(&mut self, move_struct: &Move, kind: PieceKind) {
+        let (origin_row, origin_column) = move_struct.piece_move.origin;
+        let piece_color = self.board[origin_row][origin_column].unwrap().color;
+        let promoted_piece = Piece::new(kind, piece_color);
+        let (destination_row, destination_column) = move_struct.piece_move.destination;
+        self.board[origin_row][origin_column] = None;
+        self.board[destination_row][destination_column] = Some(promoted_piece);
+    }
+
+    fn explode(&mut self, center: (usize, usize)) {
+        for row in center.0.saturating_sub(1)..=(center.0 + 1).min(7) {
+            for column in center.1.saturating_sub(1)..=(center.1 + 1).min(7) {
+                let caught_in_blast = match self.board[row][column] {
+                    Some(piece) => (row, column) == center || piece.kind != PieceKind::Pawn,
+                    None => false,
+                };
+                if caught_in_blast {
+                    self.board[row][column] = None;
+                    self.revoke_castle_rights_at((row, column));
+                }
+            }
+        }
+    }
+
+    fn revoke_castle_rights_at(&mut self, square: (usize, usize)) {
+        match square {
+            (0, 0) => self.castle_rights.white_queen_side = false,
+            (0, 7) => self.castle_rights.white_king_side = false,
+            (7, 0) => self.castle_rights.black_queen_side = false,
+            (7, 7) => self.castle_rights.black_king_side = false,
+            (0, 4) => {
+                self.castle_rights.white_king_side = false;
+                self.castle_rights.white_queen_side = false;
+            }
+            (7, 4) => {
+                self.castle_rights.black_king_side = false;
+                self.castle_rights.black_queen_side = false;
+            }
+            _ => {}
+        }
+    }
+
     fn move_piece(&mut self, origin: (usize, usize), destination: (usize, usize)) {
         let (origin_row, origin_column) = origin;
         let (destination_row, destination_column) = destination;
@@ -312,9 +605,10 @@ pub enum GameStatus {
     Ongoing,
     Checkmate(Color),
     Draw,
+    VariantWin(Color),
 }
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub struct UserMove {
     pub origin: (usize, usize),
     pub destination: (usize, usize),
@@ -333,4 +627,41 @@ impl UserMove {
             promotion_request,
         }
     }
+
+    /// Parses long algebraic notation as used by UCI, e.g. `e2e4` or `e7e8q`.
+    pub fn from_uci(text: &str) -> Result<Self, String> {
+        if !text.is_ascii() || !(4..=5).contains(&text.len()) {
+            return Err(format!("Invalid move: {}", text));
+        }
+        let origin = square_to_tuple(&text[0..2])?;
+        let destination = square_to_tuple(&text[2..4])?;
+        let promotion_request = match text[4..].chars().next() {
+            None => None,
+            Some(c) => match c.to_ascii_lowercase() {
+                'q' => Some(PieceKind::Queen),
+                'r' => Some(PieceKind::Rook),
+                'b' => Some(PieceKind::Bishop),
+                'n' => Some(PieceKind::Knight),
+                'k' => Some(PieceKind::King),
+                _ => return Err(format!("Invalid promotion piece: {}", c)),
+            },
+        };
+        Ok(UserMove::new(origin, destination, promotion_request))
+    }
+
+    pub fn to_uci(&self) -> String {
+        let mut text = tuple_to_square(Some(self.origin));
+        text.push_str(&tuple_to_square(Some(self.destination)));
+        if let Some(kind) = self.promotion_request {
+            text.push(match kind {
+                PieceKind::Queen => 'q',
+                PieceKind::Rook => 'r',
+                PieceKind::Bishop => 'b',
+                PieceKind::Knight => 'n',
+                PieceKind::King => 'k',
+                PieceKind::Pawn => 'p',
+            });
+        }
+        text
+    }
 }
@@ -36,6 +36,30 @@ pub enum PieceKind {
     Pawn,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Variant {
+    Standard,
+    Atomic,
+    Antichess,
+    Horde,
+    RacingKings,
+}
+
+impl Variant {
+    pub fn starting_fen(&self) -> &'static str {
+        match self {
+            Variant::Standard | Variant::Atomic => {
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            }
+            Variant::Antichess => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            Variant::Horde => {
+                "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+            }
+            Variant::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct CastleRights {
     pub white_queen_side: bool,
@@ -72,6 +96,7 @@ pub struct ValidMove {
     pub destination: (usize, usize),
 }
 
+#[derive(Clone)]
 pub struct Move {
     pub piece_move: UserMove,
     pub side_effects: Vec<SideEffect>,
@@ -104,6 +129,7 @@ pub enum SideEffect {
     KingMove(Color),
     InitialRookMove(RookType),
     Promotion,
+    Explosion((usize, usize)),
 }
 
 #[derive(Clone, Copy, PartialEq)]
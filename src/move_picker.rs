@@ -0,0 +1,305 @@
+use crate::{helpers::is_tactical, models::*, search::MAX_PLY, see, Board, UserMove};
+
+const MAX_HISTORY: i32 = 16_384;
+
+/// Quiet-move statistics gathered during a search and shared by every `MovePicker`.
+pub struct OrderingTables {
+    killers: Vec<[Option<UserMove>; 2]>,
+    history: Box<[[[i32; 64]; 64]; 2]>,
+    counter_moves: Box<[[Option<UserMove>; 64]; 64]>,
+}
+
+impl OrderingTables {
+    pub fn new() -> Self {
+        OrderingTables {
+            killers: vec![[None; 2]; MAX_PLY + 1],
+            history: Box::new([[[0; 64]; 64]; 2]),
+            counter_moves: Box::new([[None; 64]; 64]),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Halves history scores between searches so old statistics fade out.
+    pub fn age(&mut self) {
+        self.history
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .for_each(|score| *score /= 2);
+        self.killers.iter_mut().for_each(|slot| *slot = [None; 2]);
+    }
+
+    pub fn killers(&self, ply: usize) -> [Option<UserMove>; 2] {
+        self.killers[ply]
+    }
+
+    pub fn counter_move(&self, previous: Option<UserMove>) -> Option<UserMove> {
+        previous
+            .and_then(|m| self.counter_moves[square_index(m.origin)][square_index(m.destination)])
+    }
+
+    pub fn history(&self, color: Color, m: &UserMove) -> i32 {
+        self.history[color_index(color)][square_index(m.origin)][square_index(m.destination)]
+    }
+
+    /// Records a quiet move that caused a beta cutoff, penalising the quiets tried before it.
+    pub fn record_cutoff(
+        &mut self,
+        color: Color,
+        ply: usize,
+        depth: u32,
+        cutoff: UserMove,
+        previous: Option<UserMove>,
+        tried_quiets: &[UserMove],
+    ) {
+        if self.killers[ply][0] != Some(cutoff) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(cutoff);
+        }
+        if let Some(previous) = previous {
+            self.counter_moves[square_index(previous.origin)][square_index(previous.destination)] =
+                Some(cutoff);
+        }
+        let bonus = (depth * depth) as i32;
+        self.update_history(color, &cutoff, bonus);
+        for quiet in tried_quiets.iter().filter(|quiet| **quiet != cutoff) {
+            self.update_history(color, quiet, -bonus);
+        }
+    }
+
+    fn update_history(&mut self, color: Color, m: &UserMove, bonus: i32) {
+        let entry = &mut self.history[color_index(color)][square_index(m.origin)]
+            [square_index(m.destination)];
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+}
+
+impl Default for OrderingTables {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Stage {
+    HashMove,
+    GenerateMoves,
+    GoodCaptures,
+    Killers,
+    CounterMove,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Hands out a node's legal moves one at a time in a staged order: hash move, winning and equal
+/// captures by MVV-LVA, killer moves, the counter-move, remaining quiets by history score and
+/// finally losing captures. Moves are only generated once the hash move has been tried, and each
+/// stage is scored only when it is reached.
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<UserMove>,
+    killers: Vec<UserMove>,
+    counter_move: Option<UserMove>,
+    pregenerated: Option<Vec<Move>>,
+    good_captures: Vec<(Move, i32)>,
+    bad_captures: Vec<(Move, i32)>,
+    quiets: Vec<Move>,
+    scored_quiets: Vec<(Move, i32)>,
+    quiets_scored: bool,
+}
+
+impl MovePicker {
+    pub fn new(
+        hash_move: Option<UserMove>,
+        killers: [Option<UserMove>; 2],
+        counter_move: Option<UserMove>,
+    ) -> Self {
+        let killers: Vec<UserMove> = killers.into_iter().flatten().collect();
+        let counter_move = counter_move.filter(|m| !killers.contains(m));
+        MovePicker {
+            stage: Stage::HashMove,
+            hash_move,
+            killers,
+            counter_move,
+            pregenerated: None,
+            good_captures: Vec::new(),
+            bad_captures: Vec::new(),
+            quiets: Vec::new(),
+            scored_quiets: Vec::new(),
+            quiets_scored: false,
+        }
+    }
+
+    /// Orders an already generated move list, for callers that needed the moves up front.
+    pub fn with_moves(
+        moves: Vec<Move>,
+        hash_move: Option<UserMove>,
+        killers: [Option<UserMove>; 2],
+        counter_move: Option<UserMove>,
+    ) -> Self {
+        let mut picker = Self::new(hash_move, killers, counter_move);
+        picker.pregenerated = Some(moves);
+        picker
+    }
+
+    pub fn next(&mut self, board: &Board, tables: &OrderingTables) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateMoves;
+                    if let Some(m) = self.take_hash_move(board) {
+                        return Some(m);
+                    }
+                }
+                Stage::GenerateMoves => {
+                    self.generate(board);
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => match pop_best(&mut self.good_captures) {
+                    Some(m) => return Some(m),
+                    None => self.stage = Stage::Killers,
+                },
+                Stage::Killers => {
+                    while !self.killers.is_empty() {
+                        let killer = self.killers.remove(0);
+                        if let Some(m) = self.take_quiet(killer) {
+                            return Some(m);
+                        }
+                    }
+                    self.stage = Stage::CounterMove;
+                }
+                Stage::CounterMove => {
+                    self.stage = Stage::Quiets;
+                    if let Some(m) = self.counter_move.take().and_then(|m| self.take_quiet(m)) {
+                        return Some(m);
+                    }
+                }
+                Stage::Quiets => {
+                    if !self.quiets_scored {
+                        self.score_quiets(board, tables);
+                    }
+                    match pop_best(&mut self.scored_quiets) {
+                        Some(m) => return Some(m),
+                        None => self.stage = Stage::BadCaptures,
+                    }
+                }
+                Stage::BadCaptures => match pop_best(&mut self.bad_captures) {
+                    Some(m) => return Some(m),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    fn take_hash_move(&mut self, board: &Board) -> Option<Move> {
+        let hash_move = self.hash_move?;
+        let found = match &mut self.pregenerated {
+            Some(moves) => moves
+                .iter()
+                .position(|m| m.piece_move == hash_move)
+                .map(|index| moves.swap_remove(index)),
+            None if board.variant == Variant::Antichess => None,
+            None => board
+                .legal_moves_from_square(hash_move.origin)
+                .into_iter()
+                .find(|m| m.piece_move == hash_move),
+        };
+        if found.is_none() {
+            self.hash_move = None;
+        }
+        found
+    }
+
+    fn generate(&mut self, board: &Board) {
+        let moves = match self.pregenerated.take() {
+            Some(moves) => moves,
+            None => board.legal_moves(),
+        };
+        for m in moves {
+            if Some(m.piece_move) == self.hash_move {
+                continue;
+            }
+            if is_tactical(&m) {
+                let score = mvv_lva(board, &m);
+                if see::static_exchange(board, &m.piece_move) >= 0 {
+                    self.good_captures.push((m, score));
+                } else {
+                    self.bad_captures.push((m, score));
+                }
+            } else {
+                self.quiets.push(m);
+            }
+        }
+    }
+
+    fn take_quiet(&mut self, refutation: UserMove) -> Option<Move> {
+        let position = self
+            .quiets
+            .iter()
+            .position(|m| m.piece_move == refutation)?;
+        Some(self.quiets.swap_remove(position))
+    }
+
+    fn score_quiets(&mut self, board: &Board, tables: &OrderingTables) {
+        self.scored_quiets = self
+            .quiets
+            .drain(..)
+            .map(|m| {
+                let score = tables.history(board.player_turn, &m.piece_move);
+                (m, score)
+            })
+            .collect();
+        self.quiets_scored = true;
+    }
+}
+
+fn pop_best(moves: &mut Vec<(Move, i32)>) -> Option<Move> {
+    let index = moves
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, (_, score))| *score)
+        .map(|(index, _)| index)?;
+    Some(moves.swap_remove(index).0)
+}
+
+fn captured_piece(m: &Move) -> Option<PieceKind> {
+    m.side_effects
+        .iter()
+        .find_map(|side_effect| match side_effect {
+            SideEffect::PieceTaken(piece) => Some(piece.kind),
+            SideEffect::EnPassantTake(_) => Some(PieceKind::Pawn),
+            _ => None,
+        })
+}
+
+/// Value of the captured piece plus any promotion gain, in centipawns.
+pub fn material_gain(m: &Move) -> i32 {
+    let captured = captured_piece(m).map_or(0, see::piece_value);
+    let promoted = m.piece_move.promotion_request.map_or(0, |kind| {
+        see::piece_value(kind) - see::piece_value(PieceKind::Pawn)
+    });
+    captured + promoted
+}
+
+/// Most valuable victim first, least valuable attacker as the tie-break.
+pub fn mvv_lva(board: &Board, m: &Move) -> i32 {
+    let (row, column) = m.piece_move.origin;
+    let attacker = board.board[row][column].map_or(0, |piece| see::piece_value(piece.kind));
+    material_gain(m) * 10 - attacker.min(1000) / 100
+}
+
+fn square_index((row, column): (usize, usize)) -> usize {
+    row * 8 + column
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
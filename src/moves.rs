@@ -1,62 +1,56 @@
-use std::collections::HashSet;
-
 use crate::{helpers::*, models::*, Board};
 
 pub fn pawn_attacking_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<Move> {
-    let offset: isize = match color {
-        Color::White => 1,
-        Color::Black => -1,
-    };
     let promotion_row = match color {
         Color::White => 7,
         Color::Black => 0,
     };
-    let (row, column) = origin;
+    let row = origin.0;
     let mut side_effects: Vec<SideEffect> = vec![SideEffect::PawnMove];
     let mut moves: Vec<Move> = Vec::new();
 
-    if (row as isize + offset) as usize == promotion_row {
-        side_effects.push(SideEffect::Promotion);
-    };
-    if column != 0 {
-        let destination = ((row as isize + offset) as usize, column - 1);
-        match board.board[(row as isize + offset) as usize][column - 1] {
-            Some(p) => {
-                side_effects.push(SideEffect::PieceTaken(p));
-                moves.push(Move::new(origin, destination, side_effects.clone()));
-            }
-            None => match board.en_passant_square {
-                Some(square) => {
-                    if square == destination {
-                        side_effects.push(SideEffect::EnPassantTake((row, column - 1)));
-                        moves.push(Move::new(origin, destination, side_effects.clone()));
-                    }
-                }
-                None => {}
-            },
+    for destination in pawn_attacked_squares(color, origin) {
+        if destination.0 == promotion_row && !side_effects.contains(&SideEffect::Promotion) {
+            side_effects.push(SideEffect::Promotion);
         }
-    }
-    if column != 7 {
-        let destination = ((row as isize + offset) as usize, column + 1);
-        match board.board[(row as isize + offset) as usize][column + 1] {
+        match board.board[destination.0][destination.1] {
             Some(p) => {
-                side_effects.push(SideEffect::PieceTaken(p));
-                moves.push(Move::new(origin, destination, side_effects.clone()));
+                if p.color != *color {
+                    let mut capture_effects = side_effects.clone();
+                    capture_effects.push(SideEffect::PieceTaken(p));
+                    moves.push(Move::new(origin, destination, capture_effects));
+                }
             }
-            None => match board.en_passant_square {
-                Some(square) => {
-                    if square == destination {
-                        side_effects.push(SideEffect::EnPassantTake((row, column + 1)));
-                        moves.push(Move::new(origin, destination, side_effects.clone()));
-                    }
+            None => {
+                if board.en_passant_square == Some(destination) {
+                    let mut capture_effects = side_effects.clone();
+                    capture_effects.push(SideEffect::EnPassantTake((row, destination.1)));
+                    moves.push(Move::new(origin, destination, capture_effects));
                 }
-                None => {}
-            },
+            }
         }
     }
     moves
 }
 
+pub fn pawn_attacked_squares(color: &Color, origin: (usize, usize)) -> Vec<(usize, usize)> {
+    let offset: isize = match color {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+    let mut squares: Vec<(usize, usize)> = Vec::new();
+    for column_offset in [-1, 1] {
+        let square = (
+            origin.0 as isize + offset,
+            origin.1 as isize + column_offset,
+        );
+        if is_square_within_bounds(square) {
+            squares.push((square.0 as usize, square.1 as usize));
+        }
+    }
+    squares
+}
+
 pub fn pawn_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<Move> {
     let offset: isize = match color {
         Color::White => 1,
@@ -74,30 +68,26 @@ pub fn pawn_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<M
     let mut side_effects: Vec<SideEffect> = vec![];
     let mut moves: Vec<Move> = Vec::new();
 
-    match board.board[(row as isize + offset) as usize][column] {
-        Some(_) => {}
-        None => {
-            if (row as isize + offset) as usize == promotion_row {
-                side_effects.push(SideEffect::Promotion);
-            };
-            let destination = ((row as isize + offset) as usize, column);
-            side_effects.push(SideEffect::PawnMove);
-            moves.push(Move::new(origin, destination, side_effects.clone()));
-        }
+    if board.board[(row as isize + offset) as usize][column].is_some() {
+        return moves;
     }
+    if (row as isize + offset) as usize == promotion_row {
+        side_effects.push(SideEffect::Promotion);
+    };
+    let destination = ((row as isize + offset) as usize, column);
+    side_effects.push(SideEffect::PawnMove);
+    moves.push(Move::new(origin, destination, side_effects.clone()));
 
-    if row == pawn_starting_row {
-        match board.board[(row as isize + offset * 2) as usize][column] {
-            Some(_) => {}
-            None => {
-                let destination = ((row as isize + offset * 2) as usize, column);
-                side_effects.push(SideEffect::DoublePawnMove((
-                    (row as isize + offset) as usize,
-                    column,
-                )));
-                moves.push(Move::new(origin, destination, side_effects.clone()));
-            }
-        }
+    let on_horde_back_rank = board.variant == Variant::Horde && *color == Color::White && row == 0;
+    if (row == pawn_starting_row || on_horde_back_rank)
+        && board.board[(row as isize + offset * 2) as usize][column].is_none()
+    {
+        let destination = ((row as isize + offset * 2) as usize, column);
+        side_effects.push(SideEffect::DoublePawnMove((
+            (row as isize + offset) as usize,
+            column,
+        )));
+        moves.push(Move::new(origin, destination, side_effects.clone()));
     }
     moves
 }
@@ -177,13 +167,13 @@ pub fn rook_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<M
     let mut moves: Vec<Move> = Vec::new();
     let side_effect = match color {
         Color::White => match origin {
-            (0, 0) => Some(SideEffect::InitialRookMove(RookType::WhiteKingSide)),
-            (0, 7) => Some(SideEffect::InitialRookMove(RookType::WhiteQueenSide)),
+            (0, 7) => Some(SideEffect::InitialRookMove(RookType::WhiteKingSide)),
+            (0, 0) => Some(SideEffect::InitialRookMove(RookType::WhiteQueenSide)),
             _ => None,
         },
         Color::Black => match origin {
-            (7, 0) => Some(SideEffect::InitialRookMove(RookType::BlackKingSide)),
-            (7, 7) => Some(SideEffect::InitialRookMove(RookType::BlackQueenSide)),
+            (7, 7) => Some(SideEffect::InitialRookMove(RookType::BlackKingSide)),
+            (7, 0) => Some(SideEffect::InitialRookMove(RookType::BlackQueenSide)),
             _ => None,
         },
     };
@@ -204,9 +194,8 @@ pub fn rook_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<M
                     if piece.color != *color {
                         side_effects.push(SideEffect::PieceTaken(*piece));
                         moves.push(Move::new(origin, square, side_effects));
-                    } else {
-                        break;
                     }
+                    break;
                 }
                 None => moves.push(Move::new(origin, square, side_effects)),
             }
@@ -221,7 +210,7 @@ pub fn rook_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<M
 }
 
 pub fn bishop_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<Move> {
-    let offsets: [(isize, isize); 4] = [(-1, -1), (1, 1), (1, -1), (1, -1)];
+    let offsets: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, 1), (1, -1)];
     let mut moves: Vec<Move> = Vec::new();
     for offset in offsets {
         let mut offset_multiplier = 1;
@@ -239,9 +228,8 @@ pub fn bishop_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec
                             square,
                             vec![SideEffect::PieceTaken(*piece)],
                         ));
-                    } else {
-                        break;
                     }
+                    break;
                 }
                 None => moves.push(Move::new(origin, square, vec![])),
             }
@@ -283,9 +271,8 @@ pub fn queen_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<
                             square,
                             vec![SideEffect::PieceTaken(*piece)],
                         ));
-                    } else {
-                        break;
                     }
+                    break;
                 }
                 None => moves.push(Move::new(origin, square, vec![])),
             }
@@ -301,88 +288,81 @@ pub fn queen_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<
 
 pub fn castle_moves(board: &Board, color: &Color, origin: (usize, usize)) -> Vec<Move> {
     let mut valid_moves: Vec<Move> = Vec::new();
-    let attacked_squares = get_attacked_squares(board, board.player_turn);
-    let king_starting_square = match color {
-        Color::White => (0, 3),
-        Color::Black => (7, 3),
+    let row = match color {
+        Color::White => 0,
+        Color::Black => 7,
     };
+    let king_starting_square = (row, 4);
     if origin != king_starting_square {
         return valid_moves;
     }
-    match color {
-        Color::White => {
-            if board.castle_rights.white_king_side {
-                if king_has_clear_path_to_rook(board.board, vec![(0, 2), (0, 1)], &attacked_squares)
-                {
-                    valid_moves.push(Move::new(
-                        king_starting_square,
-                        (0, 1),
-                        vec![SideEffect::Castle((0, 0), (0, 2), *color)],
-                    ))
-                }
-            }
-            if board.castle_rights.white_queen_side {
-                if king_has_clear_path_to_rook(
-                    board.board,
-                    vec![(0, 4), (0, 5), (0, 6)],
-                    &attacked_squares,
-                ) {
-                    valid_moves.push(Move::new(
-                        king_starting_square,
-                        (0, 5),
-                        vec![SideEffect::Castle((0, 7), (0, 4), *color)],
-                    ))
-                }
-            }
-        }
-        Color::Black => {
-            if board.castle_rights.black_king_side {
-                if king_has_clear_path_to_rook(board.board, vec![(7, 2), (7, 1)], &attacked_squares)
-                {
-                    valid_moves.push(Move::new(
-                        king_starting_square,
-                        (7, 1),
-                        vec![SideEffect::Castle((7, 0), (7, 2), *color)],
-                    ))
-                }
-            }
-            if board.castle_rights.black_queen_side {
-                if king_has_clear_path_to_rook(
-                    board.board,
-                    vec![(7, 4), (7, 5), (7, 6)],
-                    &attacked_squares,
-                ) {
-                    valid_moves.push(Move::new(
-                        king_starting_square,
-                        (7, 5),
-                        vec![SideEffect::Castle((7, 7), (7, 4), *color)],
-                    ))
-                }
-            }
-        }
+    let (king_side, queen_side) = match color {
+        Color::White => (
+            board.castle_rights.white_king_side,
+            board.castle_rights.white_queen_side,
+        ),
+        Color::Black => (
+            board.castle_rights.black_king_side,
+            board.castle_rights.black_queen_side,
+        ),
+    };
+    if !king_side && !queen_side {
+        return valid_moves;
+    }
+    let enemy = color.get_opposite_color();
+    if is_square_attacked(board, king_starting_square, &enemy) {
+        return valid_moves;
+    }
+    if king_side
+        && is_own_rook(board, color, (row, 7))
+        && king_has_clear_path_to_rook(
+            board.board,
+            vec![(row, 5), (row, 6)],
+            vec![(row, 5), (row, 6)],
+            |square| is_square_attacked(board, square, &enemy),
+        )
+    {
+        valid_moves.push(Move::new(
+            king_starting_square,
+            (row, 6),
+            vec![SideEffect::Castle((row, 7), (row, 5), *color)],
+        ))
+    }
+    if queen_side
+        && is_own_rook(board, color, (row, 0))
+        && king_has_clear_path_to_rook(
+            board.board,
+            vec![(row, 1), (row, 2), (row, 3)],
+            vec![(row, 2), (row, 3)],
+            |square| is_square_attacked(board, square, &enemy),
+        )
+    {
+        valid_moves.push(Move::new(
+            king_starting_square,
+            (row, 2),
+            vec![SideEffect::Castle((row, 0), (row, 3), *color)],
+        ))
     }
     valid_moves
 }
+
+fn is_own_rook(board: &Board, color: &Color, square: (usize, usize)) -> bool {
+    board.board[square.0][square.1] == Some(Piece::new(PieceKind::Rook, *color))
+}
+
 fn king_has_clear_path_to_rook(
     board: [[Option<Piece>; 8]; 8],
-    squares: Vec<(usize, usize)>,
-    attacked_squares: &HashSet<(usize, usize)>,
+    empty_squares: Vec<(usize, usize)>,
+    safe_squares: Vec<(usize, usize)>,
+    is_attacked: impl Fn((usize, usize)) -> bool,
 ) -> bool {
-    for square in squares {
-        match board[square.0][square.1] {
-            Some(_) => {
-                return false;
-            }
-            None => {
-                if attacked_squares.contains(&square) {
-                    return false;
-                }
-            }
-        }
-    }
-    true
+    let path_is_empty = empty_squares
+        .iter()
+        .all(|square| board[square.0][square.1].is_none());
+    let path_is_safe = safe_squares.iter().all(|square| !is_attacked(*square));
+    path_is_empty && path_is_safe
 }
 
-fn is_square_within_bounds(square: (isize, isize)) -> bool {
+pub fn is_square_within_bounds(square: (isize, isize)) -> bool {
     square.0 >= 0 && square.0 <= 7 && square.1 >= 0 && square.1 <= 7
 }
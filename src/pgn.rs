@@ -0,0 +1,429 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{latex, models::*, san, Board, UserMove};
+
+/// Longest line of movetext written by `PgnGame::to_pgn`.
+const LINE_WIDTH: usize = 80;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl GameResult {
+    pub fn from_pgn(text: &str) -> Option<Self> {
+        match text {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PgnMove {
+    pub user_move: UserMove,
+    pub san: String,
+    /// Numeric annotation glyphs, with `!`, `?` and friends converted to `$1`–`$6`.
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    /// Parses a single game. Variations are skipped; only the main line is kept.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut games = parse_games(text);
+        match games.len() {
+            0 => Err("No game found".to_owned()),
+            _ => games.remove(0),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn variant(&self) -> Result<Variant, String> {
+        parse_variant_tag(self.tag("Variant"))
+    }
+
+    /// The position before the first move, from the `FEN` tag or the variant's start position.
+    pub fn start_board(&self) -> Result<Board, String> {
+        let variant = self.variant()?;
+        match self.tag("FEN") {
+            Some(fen) => Board::try_from_fen_with_variant(fen, variant),
+            None => Ok(Board::from_variant(variant)),
+        }
+    }
+
+    /// A game played from `start`, with the `Variant`, `SetUp` and `FEN` tags that the start
+    /// position needs added after `tags`.
+    pub fn from_moves(
+        mut tags: Vec<(String, String)>,
+        start: &Board,
+        moves: &[UserMove],
+        result: GameResult,
+    ) -> Result<Self, String> {
+        if start.variant != Variant::Standard {
+            tags.push(("Variant".to_owned(), variant_tag(start.variant).to_owned()));
+        }
+        let fen = start.to_fen();
+        if fen != start.variant.starting_fen() {
+            tags.push(("SetUp".to_owned(), "1".to_owned()));
+            tags.push(("FEN".to_owned(), fen));
+        }
+        let mut board = *start;
+        let mut pgn_moves = Vec::new();
+        for user_move in moves {
+            pgn_moves.push(PgnMove {
+                user_move: *user_move,
+                san: board.to_san(user_move)?,
+                nags: Vec::new(),
+                comment: None,
+            });
+            board.make_move(user_move);
+        }
+        Ok(PgnGame {
+            tags,
+            moves: pgn_moves,
+            result,
+        })
+    }
+
+    /// The position after each ply, starting with the position before the first move.
+    pub fn positions(&self) -> Result<Vec<Board>, String> {
+        let mut board = self.start_board()?;
+        let mut positions = vec![board];
+        for m in &self.moves {
+            board.make_move(&m.user_move);
+            positions.push(board);
+        }
+        Ok(positions)
+    }
+
+    /// Writes the game as PGN: the tags, then the main line with move numbers, annotation glyphs
+    /// and comments, wrapped at 80 columns.
+    pub fn to_pgn(&self) -> Result<String, String> {
+        let start = self.start_board()?;
+        let mut text = String::new();
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            text.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        text.push('\n');
+
+        let mut words: Vec<String> = Vec::new();
+        let mut number = start.num_moves;
+        let mut color = start.player_turn;
+        let mut needs_number = true;
+        for pgn_move in &self.moves {
+            match color {
+                Color::White => words.push(format!("{}.", number)),
+                Color::Black if needs_number => words.push(format!("{}...", number)),
+                Color::Black => {}
+            }
+            words.push(pgn_move.san.clone());
+            words.extend(pgn_move.nags.iter().map(|nag| format!("${}", nag)));
+            needs_number = false;
+            if let Some(comment) = &pgn_move.comment {
+                let comment = comment.replace('}', ")");
+                words.push(format!("{{{}}}", comment.trim()));
+                needs_number = true;
+            }
+            if color == Color::Black {
+                number += 1;
+            }
+            color = color.get_opposite_color();
+        }
+        words.push(self.result.to_pgn().to_owned());
+
+        let mut line = String::new();
+        for word in words.iter().flat_map(|word| word.split_whitespace()) {
+            if !line.is_empty() && line.len() + 1 + word.len() > LINE_WIDTH {
+                text.push_str(&line);
+                text.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        text.push_str(&line);
+        text.push('\n');
+        Ok(text)
+    }
+
+    /// The game as a LaTeX document with diagrams after the given plies and the moves annotated
+    /// `$220` or `$221`. See `latex::game_to_latex`.
+    pub fn to_latex(
+        &self,
+        options: &latex::LatexOptions,
+        diagram_plies: &[usize],
+    ) -> Result<String, String> {
+        latex::game_to_latex(self, options, diagram_plies)
+    }
+}
+
+/// The `number`th game of a PGN collection, counting from 1.
+pub fn select_game(text: &str, number: usize) -> Result<PgnGame, String> {
+    parse_games(text)
+        .into_iter()
+        .nth(number.max(1) - 1)
+        .ok_or(format!("There is no game {}", number))?
+}
+
+/// Parses every game in a PGN collection. A malformed game yields an error without affecting the
+/// games around it.
+pub fn parse_games(text: &str) -> Vec<Result<PgnGame, String>> {
+    let mut games = Vec::new();
+    let mut tokens = tokenize(text).into_iter().peekable();
+    while tokens.peek().is_some() {
+        let mut game_tokens = Vec::new();
+        let mut in_movetext = false;
+        while let Some(token) = tokens.peek() {
+            match token {
+                Token::Tag(_, _) if in_movetext => break,
+                Token::Result(_) => {
+                    game_tokens.push(tokens.next().unwrap());
+                    break;
+                }
+                Token::Tag(_, _) => {}
+                _ => in_movetext = true,
+            }
+            game_tokens.push(tokens.next().unwrap());
+        }
+        games.push(build_game(game_tokens));
+    }
+    games
+}
+
+pub(crate) fn parse_variant_tag(tag: Option<&str>) -> Result<Variant, String> {
+    let name = match tag {
+        Some(name) => name.to_lowercase().replace([' ', '-'], ""),
+        None => return Ok(Variant::Standard),
+    };
+    match name.as_str() {
+        "standard" | "chess" | "normal" | "" => Ok(Variant::Standard),
+        "atomic" => Ok(Variant::Atomic),
+        "antichess" | "giveaway" | "suicide" => Ok(Variant::Antichess),
+        "horde" => Ok(Variant::Horde),
+        "racingkings" => Ok(Variant::RacingKings),
+        _ => Err(format!("Unsupported variant: {}", name)),
+    }
+}
+
+fn variant_tag(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Standard => "Standard",
+        Variant::Atomic => "Atomic",
+        Variant::Antichess => "Antichess",
+        Variant::Horde => "Horde",
+        Variant::RacingKings => "Racing Kings",
+    }
+}
+
+/// Today's date in UTC, in the PGN `YYYY.MM.DD` form.
+pub(crate) fn pgn_date() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86400) as i64;
+    // Days since 1970-01-01 to a civil date, after Howard Hinnant's `civil_from_days`.
+    let shifted = days + 719468;
+    let era = shifted.div_euclid(146097);
+    let day_of_era = shifted.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = match shifted_month < 10 {
+        true => shifted_month + 3,
+        false => shifted_month - 9,
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+#[derive(Clone, Debug)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    Result(GameResult),
+    Move(String),
+    /// A closing `]` or `}` without its opening bracket.
+    Stray(char),
+}
+
+fn build_game(tokens: Vec<Token>) -> Result<PgnGame, String> {
+    let mut game = PgnGame {
+        tags: Vec::new(),
+        moves: Vec::new(),
+        result: GameResult::Unknown,
+    };
+    let mut board = None;
+    let mut variation_depth = 0;
+    for token in tokens {
+        if variation_depth > 0 {
+            match token {
+                Token::VariationStart => variation_depth += 1,
+                Token::VariationEnd => variation_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        match token {
+            Token::Tag(name, value) => game.tags.push((name, value)),
+            Token::VariationStart => variation_depth = 1,
+            Token::VariationEnd => return Err("Unbalanced ')'".to_owned()),
+            Token::Stray(c) => return Err(format!("Unbalanced '{}'", c)),
+            Token::Result(result) => game.result = result,
+            Token::Comment(comment) => {
+                if let Some(last) = game.moves.last_mut() {
+                    last.comment = Some(match last.comment.take() {
+                        Some(previous) => format!("{} {}", previous, comment),
+                        None => comment,
+                    });
+                }
+            }
+            Token::Nag(nag) => {
+                if let Some(last) = game.moves.last_mut() {
+                    last.nags.push(nag);
+                }
+            }
+            Token::Move(text) => {
+                let current = match &mut board {
+                    Some(board) => board,
+                    None => board.insert(game.start_board()?),
+                };
+                let (text, nags) = split_suffix_annotations(&text);
+                let user_move = san::from_san(current, text)
+                    .map_err(|e| format!("{} at move {}", e, game.moves.len() / 2 + 1))?;
+                let san = san::to_san(current, &user_move)?;
+                current.make_move(&user_move);
+                game.moves.push(PgnMove {
+                    user_move,
+                    san,
+                    nags,
+                    comment: None,
+                });
+            }
+        }
+    }
+    if game.tags.is_empty() && game.moves.is_empty() {
+        return Err("Empty game".to_owned());
+    }
+    Ok(game)
+}
+
+fn split_suffix_annotations(text: &str) -> (&str, Vec<u8>) {
+    let move_text = text.trim_end_matches(['!', '?']);
+    let nag = match &text[move_text.len()..] {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+    (move_text, nag.into_iter().collect())
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '[' => {
+                chars.next();
+                let content: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                if let Some((name, value)) = content.trim().split_once(char::is_whitespace) {
+                    let value = value.trim().trim_matches('"').replace("\\\"", "\"");
+                    tokens.push(Token::Tag(name.to_owned(), value));
+                }
+            }
+            '{' => {
+                chars.next();
+                let comment: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                tokens.push(Token::Comment(comment.trim().to_owned()));
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|c| *c != '\n').collect();
+                tokens.push(Token::Comment(comment[1..].trim().to_owned()));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::VariationStart);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::VariationEnd);
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "[]{}();".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                if word.is_empty() {
+                    chars.next();
+                    tokens.push(Token::Stray(c));
+                }
+                tokens.extend(word_token(&word));
+            }
+        }
+    }
+    tokens
+}
+
+fn word_token(word: &str) -> Option<Token> {
+    if let Some(result) = GameResult::from_pgn(word) {
+        return Some(Token::Result(result));
+    }
+    if let Some(nag) = word.strip_prefix('$') {
+        return nag.parse().ok().map(Token::Nag);
+    }
+    if word.starts_with("0-0") {
+        return Some(Token::Move(word.to_owned()));
+    }
+    // Move numbers such as `12.` or `12...`, possibly glued to the move as in `12.e4`.
+    let text = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if text.is_empty() {
+        return None;
+    }
+    Some(Token::Move(text.to_owned()))
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use crate::{
+    models::*,
+    move_picker::material_gain,
+    pgn::GameResult,
+    rng::Rng,
+    search::{SearchLimits, Searcher},
+    skill::Skill,
+    Board, GameStatus, MoveOutcome, UserMove,
+};
+
+/// Anything that can pick a move: a human at a prompt, an engine, a test script.
+pub trait Player {
+    /// A move for the side to move. Only called on positions with at least one legal move.
+    /// `history` holds the game's earlier positions, oldest first.
+    fn choose_move(&mut self, board: &Board, history: &[Board]) -> UserMove;
+}
+
+/// Plays a uniformly random legal move.
+pub struct RandomPlayer {
+    rng: Rng,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> Self {
+        RandomPlayer {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn choose_move(&mut self, board: &Board, _history: &[Board]) -> UserMove {
+        let moves = board.legal_moves();
+        moves[self.rng.below(moves.len() as u64) as usize].piece_move
+    }
+}
+
+/// Takes the most valuable capture or promotion on offer, choosing at random between equally
+/// good moves and among all moves when nothing can be won.
+pub struct GreedyPlayer {
+    rng: Rng,
+}
+
+impl GreedyPlayer {
+    pub fn new(seed: u64) -> Self {
+        GreedyPlayer {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Player for GreedyPlayer {
+    fn choose_move(&mut self, board: &Board, _history: &[Board]) -> UserMove {
+        let moves = board.legal_moves();
+        let best_gain = moves.iter().map(material_gain).max().unwrap_or(0);
+        let best: Vec<UserMove> = moves
+            .iter()
+            .filter(|m| material_gain(m) == best_gain)
+            .map(|m| m.piece_move)
+            .collect();
+        best[self.rng.below(best.len() as u64) as usize]
+    }
+}
+
+/// Searches every move with the same limits, keeping its transposition table between moves.
+pub struct EnginePlayer {
+    searcher: Searcher,
+    limits: SearchLimits,
+    skill: Skill,
+    rng: Rng,
+}
+
+impl EnginePlayer {
+    pub fn new(limits: SearchLimits) -> Self {
+        EnginePlayer {
+            searcher: Searcher::new(limits.clone()),
+            limits,
+            skill: Skill::full_strength(),
+            rng: Rng::new(0),
+        }
+    }
+
+    /// Plays at reduced strength, with choices drawn from `seed`.
+    pub fn with_skill(limits: SearchLimits, skill: Skill, seed: u64) -> Self {
+        EnginePlayer {
+            skill,
+            rng: Rng::new(seed),
+            ..Self::new(limits)
+        }
+    }
+
+    pub fn searcher_mut(&mut self) -> &mut Searcher {
+        &mut self.searcher
+    }
+}
+
+impl Player for EnginePlayer {
+    fn choose_move(&mut self, board: &Board, history: &[Board]) -> UserMove {
+        self.searcher
+            .set_limits(self.skill.limits(self.limits.clone()));
+        self.searcher.set_history(history);
+        let result = self
+            .skill
+            .choose(self.searcher.search(board), &mut self.rng);
+        match result.best_move {
+            Some(best_move) => best_move,
+            None => board.legal_moves()[0].piece_move,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Termination {
+    Checkmate,
+    /// Stalemate, the fifty-move rule or any other draw the board itself declares.
+    Draw,
+    Repetition,
+    VariantWin,
+    /// The player of this colour chose an illegal move and forfeits.
+    IllegalMove(Color),
+    /// The player of this colour ran out of time.
+    TimeForfeit(Color),
+    /// The engine playing this colour crashed or closed its connection.
+    Disconnected(Color),
+    /// This colour resigned, or was adjudicated lost after both engines agreed on a decisive
+    /// score.
+    Resignation(Color),
+    /// Adjudicated as drawn after both engines kept reporting a level score.
+    AdjudicatedDraw,
+    /// Adjudicated by an endgame table.
+    Tablebase,
+    /// Adjudicated as drawn after the move limit.
+    MoveLimit,
+}
+
+impl Termination {
+    /// The value of the PGN `Termination` tag.
+    pub fn pgn_tag(&self) -> &'static str {
+        match self {
+            Termination::Checkmate
+            | Termination::Draw
+            | Termination::Repetition
+            | Termination::VariantWin => "normal",
+            Termination::IllegalMove(_) => "rules infraction",
+            Termination::TimeForfeit(_) => "time forfeit",
+            Termination::Disconnected(_) => "abandoned",
+            Termination::Resignation(_)
+            | Termination::AdjudicatedDraw
+            | Termination::Tablebase
+            | Termination::MoveLimit => "adjudication",
+        }
+    }
+}
+
+impl std::fmt::Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Termination::Checkmate => write!(f, "checkmate"),
+            Termination::Draw => write!(f, "draw"),
+            Termination::Repetition => write!(f, "threefold repetition"),
+            Termination::VariantWin => write!(f, "variant win"),
+            Termination::IllegalMove(color) => write!(f, "illegal move by {}", color_name(*color)),
+            Termination::TimeForfeit(color) => write!(f, "{} loses on time", color_name(*color)),
+            Termination::Disconnected(color) => write!(f, "{} disconnects", color_name(*color)),
+            Termination::Resignation(color) => write!(f, "{} resigns", color_name(*color)),
+            Termination::AdjudicatedDraw => write!(f, "draw by adjudication"),
+            Termination::Tablebase => write!(f, "tablebase adjudication"),
+            Termination::MoveLimit => write!(f, "move limit"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PlayedGame {
+    pub start: Board,
+    pub moves: Vec<UserMove>,
+    pub result: GameResult,
+    pub termination: Termination,
+}
+
+impl PlayedGame {
+    pub fn final_board(&self) -> Board {
+        let mut board = self.start;
+        for m in &self.moves {
+            board.make_move(m);
+        }
+        board
+    }
+}
+
+/// Alternates between the two players from `start` until the game is decided, a position occurs
+/// for the third time, or a player returns an illegal move.
+pub fn play_game(white: &mut dyn Player, black: &mut dyn Player, start: Board) -> PlayedGame {
+    let mut board = start;
+    let mut moves = Vec::new();
+    let mut history = Vec::new();
+    let mut seen: HashMap<u64, u32> = HashMap::new();
+    let (result, termination) = loop {
+        if let Some(decided) = decided(&board, &mut seen) {
+            break decided;
+        }
+
+        let mover = board.player_turn;
+        let chosen = match mover {
+            Color::White => white.choose_move(&board, &history),
+            Color::Black => black.choose_move(&board, &history),
+        };
+        history.push(board);
+        match board.make_move(&chosen) {
+            MoveOutcome::Success => moves.push(chosen),
+            _ => {
+                let winner = mover.get_opposite_color();
+                break (win_for(winner), Termination::IllegalMove(mover));
+            }
+        }
+    };
+    PlayedGame {
+        start,
+        moves,
+        result,
+        termination,
+    }
+}
+
+/// The result if the game is over by the rules, counting `board` as one more occurrence of its
+/// position in `seen`.
+pub(crate) fn decided(
+    board: &Board,
+    seen: &mut HashMap<u64, u32>,
+) -> Option<(GameResult, Termination)> {
+    match board.game_status() {
+        GameStatus::Ongoing => {}
+        GameStatus::Checkmate(winner) => return Some((win_for(winner), Termination::Checkmate)),
+        GameStatus::VariantWin(winner) => return Some((win_for(winner), Termination::VariantWin)),
+        GameStatus::Draw => return Some((GameResult::Draw, Termination::Draw)),
+    }
+    let occurrences = seen.entry(board.hash()).or_insert(0);
+    *occurrences += 1;
+    match *occurrences >= 3 {
+        true => Some((GameResult::Draw, Termination::Repetition)),
+        false => None,
+    }
+}
+
+pub(crate) fn win_for(color: Color) -> GameResult {
+    match color {
+        Color::White => GameResult::WhiteWins,
+        Color::Black => GameResult::BlackWins,
+    }
+}
+
+pub(crate) fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    }
+}
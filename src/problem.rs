@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+
+use crate::{helpers::is_checked, Board, GameStatus, UserMove};
+
+/// What a chess problem asks for. Counts are in moves of the side that starts.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stipulation {
+    /// `#N`: the side to move forces mate in at most N moves against any defence.
+    Mate(u32),
+    /// `h#N`: the side to move helps the other side mate it in N moves each.
+    Helpmate(u32),
+    /// `s#N`: the side to move forces the other side to mate it within N moves.
+    Selfmate(u32),
+}
+
+impl Stipulation {
+    /// Parses the usual notation: `#2`, `h#3`, `s#4`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid stipulation: {}", text);
+        let (kind, count) = text.split_once('#').ok_or_else(invalid)?;
+        let count: u32 = count.parse().map_err(|_| invalid())?;
+        if count == 0 {
+            return Err(invalid());
+        }
+        match kind.to_lowercase().as_str() {
+            "" => Ok(Stipulation::Mate(count)),
+            "h" => Ok(Stipulation::Helpmate(count)),
+            "s" => Ok(Stipulation::Selfmate(count)),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn moves(&self) -> u32 {
+        match self {
+            Stipulation::Mate(moves)
+            | Stipulation::Helpmate(moves)
+            | Stipulation::Selfmate(moves) => *moves,
+        }
+    }
+}
+
+impl std::fmt::Display for Stipulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Stipulation::Mate(moves) => write!(f, "#{}", moves),
+            Stipulation::Helpmate(moves) => write!(f, "h#{}", moves),
+            Stipulation::Selfmate(moves) => write!(f, "s#{}", moves),
+        }
+    }
+}
+
+/// A defence to a key move and every attacking reply that still fulfils the stipulation.
+#[derive(Clone, Debug)]
+pub struct Defence {
+    pub user_move: UserMove,
+    pub san: String,
+    pub continuations: Vec<(UserMove, String)>,
+}
+
+impl Defence {
+    /// More than one continuation: the composition has a dual after this defence.
+    pub fn is_dual(&self) -> bool {
+        self.continuations.len() > 1
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyMove {
+    pub user_move: UserMove,
+    pub san: String,
+    /// Fewest moves in which the key fulfils the stipulation, which may be shorter than asked.
+    pub moves: u32,
+    /// Every defence with its continuations, for mates and selfmates longer than one move.
+    pub defences: Vec<Defence>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProblemReport {
+    pub stipulation: Stipulation,
+    pub keys: Vec<KeyMove>,
+    /// Full solutions in SAN, for helpmates.
+    pub lines: Vec<Vec<String>>,
+    pub nodes: u64,
+}
+
+impl ProblemReport {
+    /// A sound problem has exactly one solution; anything beyond that is a cook.
+    pub fn is_sound(&self) -> bool {
+        match self.stipulation {
+            Stipulation::Helpmate(_) => self.lines.len() == 1,
+            _ => self.keys.len() == 1,
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn duals(&self) -> Vec<(&KeyMove, &Defence)> {
+        self.keys
+            .iter()
+            .flat_map(|key| {
+                key.defences
+                    .iter()
+                    .filter(|defence| defence.is_dual())
+                    .map(move |defence| (key, defence))
+            })
+            .collect()
+    }
+}
+
+/// Finds every solution to `stipulation` by exhaustive search over the legal moves, with
+/// positions already decided remembered by hash.
+pub fn solve(board: &Board, stipulation: Stipulation) -> Result<ProblemReport, String> {
+    if stipulation.moves() == 0 {
+        return Err("A stipulation needs at least one move".to_owned());
+    }
+    if !matches!(board.game_status(), GameStatus::Ongoing) {
+        return Err("The game is already over".to_owned());
+    }
+    let mut solver = Solver {
+        memo: HashMap::new(),
+        nodes: 0,
+    };
+    let mut report = ProblemReport {
+        stipulation,
+        keys: Vec::new(),
+        lines: Vec::new(),
+        nodes: 0,
+    };
+    match stipulation {
+        Stipulation::Mate(moves) | Stipulation::Selfmate(moves) => {
+            let goal = match stipulation {
+                Stipulation::Mate(_) => Goal::Mate,
+                _ => Goal::Selfmate,
+            };
+            for (key, after) in solver.children(board) {
+                let Some(fewest) = (1..=moves).find(|n| solver.key_works(goal, &after, *n)) else {
+                    continue;
+                };
+                let defences = match fewest {
+                    1 => Vec::new(),
+                    _ => solver.defences(goal, &after, fewest - 1)?,
+                };
+                report.keys.push(KeyMove {
+                    user_move: key,
+                    san: board.to_san(&key)?,
+                    moves: fewest,
+                    defences,
+                });
+            }
+        }
+        Stipulation::Helpmate(moves) => {
+            let mut line = Vec::new();
+            solver.helpmate_lines(board, moves, &mut line, &mut report.lines)?;
+            for first in report.lines.iter().map(|line| line[0].clone()) {
+                if report.keys.iter().any(|key| key.san == first) {
+                    continue;
+                }
+                report.keys.push(KeyMove {
+                    user_move: board.parse_san(&first)?,
+                    san: first,
+                    moves,
+                    defences: Vec::new(),
+                });
+            }
+        }
+    }
+    report.nodes = solver.nodes;
+    Ok(report)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Goal {
+    Mate,
+    Selfmate,
+    Helpmate,
+}
+
+struct Solver {
+    memo: HashMap<(u64, u32, Goal), bool>,
+    nodes: u64,
+}
+
+impl Solver {
+    fn children(&mut self, board: &Board) -> Vec<(UserMove, Board)> {
+        board
+            .legal_moves()
+            .into_iter()
+            .map(|m| {
+                self.nodes += 1;
+                let mut after = *board;
+                after.apply_move(&m);
+                (m.piece_move, after)
+            })
+            .collect()
+    }
+
+    /// Checkmate of the side to move. Only positions with the side to move in check are worth
+    /// generating moves for.
+    fn is_mated(&self, board: &Board) -> bool {
+        is_checked(board) && matches!(board.game_status(), GameStatus::Checkmate(_))
+    }
+
+    /// Checkmate, stalemate or any other end of the game, after which nobody can mate.
+    fn is_over(&self, board: &Board) -> bool {
+        !matches!(board.game_status(), GameStatus::Ongoing)
+    }
+
+    /// The position after the attacker's key move: does it fulfil `goal` within `moves`?
+    fn key_works(&mut self, goal: Goal, after: &Board, moves: u32) -> bool {
+        match goal {
+            Goal::Mate => {
+                self.is_mated(after)
+                    || !self.is_over(after) && self.defended(goal, after, moves - 1)
+            }
+            _ => !self.is_over(after) && self.defended(goal, after, moves - 1),
+        }
+    }
+
+    /// The defender is to move: does every defence still lose within `moves` more attacking
+    /// moves? For selfmates, a defender that mates has lost too.
+    fn defended(&mut self, goal: Goal, board: &Board, moves: u32) -> bool {
+        if goal == Goal::Mate && moves == 0 {
+            return false;
+        }
+        for (_, after) in self.children(board) {
+            let fulfilled = match goal {
+                Goal::Selfmate if self.is_mated(&after) => true,
+                _ if moves == 0 || self.is_over(&after) => false,
+                _ => self.forces(goal, &after, moves),
+            };
+            if !fulfilled {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The attacker is to move: can it fulfil `goal` within `moves` moves?
+    fn forces(&mut self, goal: Goal, board: &Board, moves: u32) -> bool {
+        let key = (board.hash(), moves, goal);
+        if let Some(result) = self.memo.get(&key) {
+            return *result;
+        }
+        let mut result = false;
+        for (_, after) in self.children(board) {
+            if self.key_works(goal, &after, moves) {
+                result = true;
+                break;
+            }
+        }
+        self.memo.insert(key, result);
+        result
+    }
+
+    fn defences(
+        &mut self,
+        goal: Goal,
+        after_key: &Board,
+        moves: u32,
+    ) -> Result<Vec<Defence>, String> {
+        let mut defences = Vec::new();
+        for (defence, after) in self.children(after_key) {
+            let mut continuations = Vec::new();
+            let decided = match goal {
+                Goal::Selfmate => self.is_mated(&after),
+                _ => false,
+            };
+            if !decided && !self.is_over(&after) {
+                for (continuation, next) in self.children(&after) {
+                    let works = (1..=moves).any(|n| self.key_works(goal, &next, n));
+                    if works {
+                        continuations.push((continuation, after.to_san(&continuation)?));
+                    }
+                }
+            }
+            defences.push(Defence {
+                user_move: defence,
+                san: after_key.to_san(&defence)?,
+                continuations,
+            });
+        }
+        Ok(defences)
+    }
+
+    /// Can the side to move be mated by the other side's `moves`-th reply with both sides
+    /// cooperating?
+    fn helpmate_exists(&mut self, board: &Board, moves: u32) -> bool {
+        let key = (board.hash(), moves, Goal::Helpmate);
+        if let Some(result) = self.memo.get(&key) {
+            return *result;
+        }
+        let mut result = false;
+        'search: for (_, after) in self.children(board) {
+            if self.is_over(&after) {
+                continue;
+            }
+            for (_, next) in self.children(&after) {
+                let mated = match moves {
+                    1 => self.is_mated(&next),
+                    _ => !self.is_over(&next) && self.helpmate_exists(&next, moves - 1),
+                };
+                if mated {
+                    result = true;
+                    break 'search;
+                }
+            }
+        }
+        self.memo.insert(key, result);
+        result
+    }
+
+    fn helpmate_lines(
+        &mut self,
+        board: &Board,
+        moves: u32,
+        line: &mut Vec<String>,
+        lines: &mut Vec<Vec<String>>,
+    ) -> Result<(), String> {
+        if !self.helpmate_exists(board, moves) {
+            return Ok(());
+        }
+        for (first, after) in self.children(board) {
+            if self.is_over(&after) {
+                continue;
+            }
+            line.push(board.to_san(&first)?);
+            for (second, next) in self.children(&after) {
+                line.push(after.to_san(&second)?);
+                if moves == 1 {
+                    if self.is_mated(&next) {
+                        lines.push(line.clone());
+                    }
+                } else if !self.is_over(&next) {
+                    self.helpmate_lines(&next, moves - 1, line, lines)?;
+                }
+                line.pop();
+            }
+            line.pop();
+        }
+        Ok(())
+    }
+}
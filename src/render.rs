@@ -0,0 +1,266 @@
+use std::fmt;
+
+use crate::{
+    helpers::{get_king_square, is_checked},
+    models::*,
+    Board, UserMove,
+};
+
+const RESET: &str = "\x1b[0m";
+const LIGHT_SQUARE: &str = "\x1b[48;5;223m";
+const DARK_SQUARE: &str = "\x1b[48;5;137m";
+const LAST_MOVE_SQUARE: &str = "\x1b[48;5;186m";
+const MARKED_SQUARE: &str = "\x1b[48;5;110m";
+const CHECK_SQUARE: &str = "\x1b[48;5;167m";
+const WHITE_PIECE: &str = "\x1b[38;5;231m";
+const BLACK_PIECE: &str = "\x1b[38;5;16m";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BoardStyle {
+    /// FEN letters, upper case for white, with `.` for empty squares.
+    Ascii,
+    /// Chess figurines, with `·` for empty squares.
+    Unicode,
+}
+
+/// How to draw a board as text.
+///
+/// Without colours, highlighted squares are bracketed: `[ ]` for the last move, `< >` for
+/// marked squares and `( )` for a king in check. With colours they get their own background.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    pub style: BoardStyle,
+    /// Rank numbers on the left and file letters underneath.
+    pub coordinates: bool,
+    /// The side shown at the bottom.
+    pub orientation: Color,
+    /// ANSI colours for the squares, pieces and highlights, for terminals that support them.
+    pub colors: bool,
+    pub last_move: Option<UserMove>,
+    /// Highlights the king of the side to move when it is in check.
+    pub check: bool,
+    /// Squares to point out, as `(row, column)` like `UserMove`.
+    pub marked: Vec<(usize, usize)>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            style: BoardStyle::Ascii,
+            coordinates: true,
+            orientation: Color::White,
+            colors: false,
+            last_move: None,
+            check: true,
+            marked: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Highlight {
+    None,
+    LastMove,
+    Marked,
+    Check,
+}
+
+pub fn render(board: &Board, options: &RenderOptions) -> String {
+    let checked_king = match options.check && is_checked(board) {
+        true => get_king_square(board.board, &board.player_turn),
+        false => None,
+    };
+    let highlight = |square: (usize, usize)| {
+        if checked_king == Some(square) {
+            Highlight::Check
+        } else if options.marked.contains(&square) {
+            Highlight::Marked
+        } else if options
+            .last_move
+            .is_some_and(|m| m.origin == square || m.destination == square)
+        {
+            Highlight::LastMove
+        } else {
+            Highlight::None
+        }
+    };
+    let columns: Vec<usize> = match options.orientation {
+        Color::White => (0..8).collect(),
+        Color::Black => (0..8).rev().collect(),
+    };
+
+    let mut text = String::new();
+    for rank in 0..8 {
+        let row = match options.orientation {
+            Color::White => 7 - rank,
+            Color::Black => rank,
+        };
+        let mut line = String::new();
+        if options.coordinates {
+            line.push_str(&format!("{} ", row + 1));
+        }
+        for &column in &columns {
+            let square = (row, column);
+            line.push_str(&cell(
+                board.board[row][column],
+                square,
+                highlight(square),
+                options,
+            ));
+        }
+        match options.colors {
+            true => line.push_str(RESET),
+            false => line.truncate(line.trim_end().len()),
+        }
+        text.push_str(&line);
+        text.push('\n');
+    }
+    if options.coordinates {
+        let files: Vec<String> = columns
+            .iter()
+            .map(|column| format!(" {} ", (b'a' + *column as u8) as char))
+            .collect();
+        text.push_str(format!("  {}", files.concat()).trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// One square, three characters wide.
+fn cell(
+    piece: Option<Piece>,
+    (row, column): (usize, usize),
+    highlight: Highlight,
+    options: &RenderOptions,
+) -> String {
+    if !options.colors {
+        let symbol = match (piece, options.style) {
+            (Some(piece), BoardStyle::Ascii) => piece_letter(&piece),
+            (Some(piece), BoardStyle::Unicode) => figurine(piece.kind, piece.color),
+            (None, BoardStyle::Ascii) => '.',
+            (None, BoardStyle::Unicode) => '·',
+        };
+        let (open, close) = match highlight {
+            Highlight::None => (' ', ' '),
+            Highlight::LastMove => ('[', ']'),
+            Highlight::Marked => ('<', '>'),
+            Highlight::Check => ('(', ')'),
+        };
+        return format!("{}{}{}", open, symbol, close);
+    }
+
+    let background = match highlight {
+        Highlight::None if (row + column) % 2 == 1 => LIGHT_SQUARE,
+        Highlight::None => DARK_SQUARE,
+        Highlight::LastMove => LAST_MOVE_SQUARE,
+        Highlight::Marked => MARKED_SQUARE,
+        Highlight::Check => CHECK_SQUARE,
+    };
+    let Some(piece) = piece else {
+        return format!("{}   ", background);
+    };
+    let foreground = match piece.color {
+        Color::White => WHITE_PIECE,
+        Color::Black => BLACK_PIECE,
+    };
+    // The solid figurines read best on coloured squares, with the colour telling the sides apart.
+    let symbol = match options.style {
+        BoardStyle::Ascii => piece_letter(&piece),
+        BoardStyle::Unicode => figurine(piece.kind, Color::Black),
+    };
+    format!("{}{} {} ", background, foreground, symbol)
+}
+
+/// The diagram style of printed books: a framed board with FEN letters, dots on the empty dark
+/// squares, coordinates and a caption saying who is to move.
+pub fn book_diagram(board: &Board, orientation: Color) -> String {
+    let columns: Vec<usize> = match orientation {
+        Color::White => (0..8).collect(),
+        Color::Black => (0..8).rev().collect(),
+    };
+    let frame = format!("   +{}+\n", "-".repeat(24));
+    let mut text = frame.clone();
+    for rank in 0..8 {
+        let row = match orientation {
+            Color::White => 7 - rank,
+            Color::Black => rank,
+        };
+        text.push_str(&format!(" {} |", row + 1));
+        for &column in &columns {
+            let symbol = match board.board[row][column] {
+                Some(piece) => piece_letter(&piece),
+                None if (row + column) % 2 == 0 => '.',
+                None => ' ',
+            };
+            text.push_str(&format!(" {} ", symbol));
+        }
+        text.push_str("|\n");
+    }
+    text.push_str(&frame);
+    let files: Vec<String> = columns
+        .iter()
+        .map(|column| format!(" {} ", (b'a' + *column as u8) as char))
+        .collect();
+    text.push_str(&format!("    {}\n", files.concat().trim_end()));
+    let side = match board.player_turn {
+        Color::White => "White",
+        Color::Black => "Black",
+    };
+    text.push_str(&format!("\n{} to move\n", side));
+    text
+}
+
+/// The FEN letter for a piece.
+pub fn piece_letter(piece: &Piece) -> char {
+    let letter = match piece.kind {
+        PieceKind::King => 'k',
+        PieceKind::Queen => 'q',
+        PieceKind::Rook => 'r',
+        PieceKind::Bishop => 'b',
+        PieceKind::Knight => 'n',
+        PieceKind::Pawn => 'p',
+    };
+    match piece.color {
+        Color::White => letter.to_ascii_uppercase(),
+        Color::Black => letter,
+    }
+}
+
+pub fn figurine(kind: PieceKind, color: Color) -> char {
+    match (color, kind) {
+        (Color::White, PieceKind::King) => '♔',
+        (Color::White, PieceKind::Queen) => '♕',
+        (Color::White, PieceKind::Rook) => '♖',
+        (Color::White, PieceKind::Bishop) => '♗',
+        (Color::White, PieceKind::Knight) => '♘',
+        (Color::White, PieceKind::Pawn) => '♙',
+        (Color::Black, PieceKind::King) => '♚',
+        (Color::Black, PieceKind::Queen) => '♛',
+        (Color::Black, PieceKind::Rook) => '♜',
+        (Color::Black, PieceKind::Bishop) => '♝',
+        (Color::Black, PieceKind::Knight) => '♞',
+        (Color::Black, PieceKind::Pawn) => '♟',
+    }
+}
+
+/// Draws the board from white's side with coordinates: in ASCII by default, with figurines for
+/// the alternate form `{:#}`.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let options = RenderOptions {
+            style: match f.alternate() {
+                true => BoardStyle::Unicode,
+                false => BoardStyle::Ascii,
+            },
+            ..Default::default()
+        };
+        write!(f, "{}", render(self, &options))
+    }
+}
+
+/// The diagram followed by the FEN, so that failed assertions show the position both ways.
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self, self.to_fen())
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{
+    helpers::{is_capture, is_castle, is_checked, tuple_to_square},
+    models::*,
+    Board, GameStatus, UserMove,
+};
+
+/// Standard algebraic notation for a legal move, e.g. `Nbd7`, `exd5`, `O-O`, `e8=Q+` or `Qh7#`.
+pub fn to_san(board: &Board, user_move: &UserMove) -> Result<String, String> {
+    let legal_moves = board.legal_moves();
+    let m = legal_moves
+        .iter()
+        .find(|m| m.piece_move == *user_move)
+        .ok_or(format!("Illegal move: {}", user_move.to_uci()))?;
+    let (row, column) = user_move.origin;
+    let piece = board.board[row][column].ok_or("No piece on the origin square".to_owned())?;
+
+    let mut san = String::new();
+    if m.side_effects.iter().any(is_castle) {
+        san.push_str(if user_move.destination.1 > column {
+            "O-O"
+        } else {
+            "O-O-O"
+        });
+    } else {
+        let capture = is_capture(m);
+        match piece.kind {
+            PieceKind::Pawn => {
+                if capture {
+                    san.push(file_char(column));
+                }
+            }
+            kind => {
+                san.push(piece_letter(kind));
+                san.push_str(&disambiguation(board, &legal_moves, user_move, kind));
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&tuple_to_square(Some(user_move.destination)));
+        if let Some(kind) = user_move.promotion_request {
+            san.push('=');
+            san.push(piece_letter(kind));
+        }
+    }
+
+    let mut after = *board;
+    after.make_move(user_move);
+    match after.game_status() {
+        GameStatus::Checkmate(_) => san.push('#'),
+        _ if is_checked(&after) => san.push('+'),
+        _ => {}
+    }
+    Ok(san)
+}
+
+/// Parses standard algebraic notation, tolerating missing or extra check marks, annotation
+/// glyphs such as `!?`, `0-0` for castling and promotions written without `=`.
+pub fn from_san(board: &Board, text: &str) -> Result<UserMove, String> {
+    let trimmed = text.trim_end_matches(['+', '#', '!', '?']);
+    let legal_moves = board.legal_moves();
+
+    if matches!(trimmed, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+        let king_side = trimmed.len() == 3;
+        return legal_moves
+            .iter()
+            .find(|m| {
+                m.side_effects.iter().any(is_castle)
+                    && (m.piece_move.destination.1 > m.piece_move.origin.1) == king_side
+            })
+            .map(|m| m.piece_move)
+            .ok_or(format!("Illegal move: {}", text));
+    }
+
+    let mut chars: Vec<char> = trimmed.chars().filter(|c| *c != 'x' && *c != '-').collect();
+    let promotion = match chars.last() {
+        Some(c) if c.is_ascii_uppercase() => {
+            let kind = parse_piece_letter(*c).ok_or(format!("Invalid move: {}", text))?;
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+            Some(kind)
+        }
+        _ => None,
+    };
+    let kind = match chars.first() {
+        Some(c) if c.is_ascii_uppercase() => {
+            let kind = parse_piece_letter(*c).ok_or(format!("Invalid move: {}", text))?;
+            chars.remove(0);
+            kind
+        }
+        _ => PieceKind::Pawn,
+    };
+    if chars.len() < 2 {
+        return Err(format!("Invalid move: {}", text));
+    }
+    let destination: String = chars[chars.len() - 2..].iter().collect();
+    let destination = square_from_text(&destination).ok_or(format!("Invalid move: {}", text))?;
+    let hint = &chars[..chars.len() - 2];
+    let mut hint_file = None;
+    let mut hint_rank = None;
+    for c in hint {
+        match c {
+            'a'..='h' => hint_file = Some(*c as usize - 'a' as usize),
+            '1'..='8' => hint_rank = Some(*c as usize - '1' as usize),
+            _ => return Err(format!("Invalid move: {}", text)),
+        }
+    }
+
+    let candidates: Vec<UserMove> = legal_moves
+        .iter()
+        .map(|m| m.piece_move)
+        .filter(|m| {
+            let (row, column) = m.origin;
+            m.destination == destination
+                && board.board[row][column].is_some_and(|piece| piece.kind == kind)
+                && hint_file.is_none_or(|file| file == column)
+                && hint_rank.is_none_or(|rank| rank == row)
+                && m.promotion_request == promotion
+        })
+        .collect();
+    match candidates.as_slice() {
+        [user_move] => Ok(*user_move),
+        [] => Err(format!("Illegal move: {}", text)),
+        _ => Err(format!("Ambiguous move: {}", text)),
+    }
+}
+
+pub fn piece_letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::King => 'K',
+        PieceKind::Queen => 'Q',
+        PieceKind::Rook => 'R',
+        PieceKind::Bishop => 'B',
+        PieceKind::Knight => 'N',
+        PieceKind::Pawn => 'P',
+    }
+}
+
+fn parse_piece_letter(letter: char) -> Option<PieceKind> {
+    match letter {
+        'K' => Some(PieceKind::King),
+        'Q' => Some(PieceKind::Queen),
+        'R' => Some(PieceKind::Rook),
+        'B' => Some(PieceKind::Bishop),
+        'N' => Some(PieceKind::Knight),
+        'P' => Some(PieceKind::Pawn),
+        _ => None,
+    }
+}
+
+fn file_char(column: usize) -> char {
+    (b'a' + column as u8) as char
+}
+
+fn square_from_text(text: &str) -> Option<(usize, usize)> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(file @ 'a'..='h'), Some(rank @ '1'..='8')) => {
+            Some((rank as usize - '1' as usize, file as usize - 'a' as usize))
+        }
+        _ => None,
+    }
+}
+
+/// The origin file, rank or square needed to tell `user_move` apart from other moves of the same
+/// piece kind to the same square.
+fn disambiguation(
+    board: &Board,
+    legal_moves: &[Move],
+    user_move: &UserMove,
+    kind: PieceKind,
+) -> String {
+    let rivals: Vec<(usize, usize)> = legal_moves
+        .iter()
+        .map(|m| m.piece_move)
+        .filter(|m| {
+            let (row, column) = m.origin;
+            m.destination == user_move.destination
+                && m.origin != user_move.origin
+                && board.board[row][column].is_some_and(|piece| piece.kind == kind)
+        })
+        .map(|m| m.origin)
+        .collect();
+    if rivals.is_empty() {
+        return String::new();
+    }
+    let (row, column) = user_move.origin;
+    if rivals.iter().all(|rival| rival.1 != column) {
+        file_char(column).to_string()
+    } else if rivals.iter().all(|rival| rival.0 != row) {
+        (row + 1).to_string()
+    } else {
+        tuple_to_square(Some(user_move.origin))
+    }
+}
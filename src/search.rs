@@ -0,0 +1,945 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    endgame::{Tablebase, Wdl},
+    eval,
+    helpers::{is_checked, is_tactical},
+    models::*,
+    move_picker::{material_gain, mvv_lva, MovePicker, OrderingTables},
+    see,
+    timeman::{Clock, TimeManager},
+    tt::{Bound, TranspositionTable, TtStats},
+    Board, GameStatus, UserMove,
+};
+
+pub const MATE_SCORE: i32 = 32_000;
+pub const MAX_PLY: usize = 128;
+const INFINITE_SCORE: i32 = MATE_SCORE + 1;
+const NODES_BETWEEN_CLOCK_CHECKS: u64 = 1024;
+const DELTA_MARGIN: i32 = 200;
+const ASPIRATION_MIN_DEPTH: u32 = 4;
+const ASPIRATION_WINDOW: i32 = 40;
+const NULL_MOVE_MIN_DEPTH: u32 = 3;
+const NULL_MOVE_REDUCTION: u32 = 2;
+const REVERSE_FUTILITY_DEPTH: u32 = 3;
+const REVERSE_FUTILITY_MARGIN: i32 = 120;
+const FUTILITY_DEPTH: u32 = 2;
+const FUTILITY_MARGIN: i32 = 180;
+const LMR_MIN_DEPTH: u32 = 3;
+const LMR_MIN_MOVES: usize = 3;
+
+#[derive(Clone, Default, Debug)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    pub clock: Option<Clock>,
+    /// Stop as soon as a mate in at most this many moves has been found.
+    pub mate: Option<u32>,
+    /// Restricts the root to these moves when not empty.
+    pub search_moves: Vec<UserMove>,
+    /// Number of best root moves to search lines for; 0 and 1 both mean a single line.
+    pub multi_pv: usize,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..Default::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        SearchLimits {
+            time: Some(time),
+            ..Default::default()
+        }
+    }
+
+    pub fn clock(clock: Clock) -> Self {
+        SearchLimits {
+            clock: Some(clock),
+            ..Default::default()
+        }
+    }
+}
+
+/// Switches for the individual pruning, reduction and extension techniques, so they can be
+/// compared against each other. Everything is enabled by default.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchOptions {
+    pub null_move: bool,
+    pub late_move_reductions: bool,
+    pub futility: bool,
+    pub reverse_futility: bool,
+    pub aspiration_windows: bool,
+    pub check_extensions: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            null_move: true,
+            late_move_reductions: true,
+            futility: true,
+            reverse_futility: true,
+            aspiration_windows: true,
+            check_extensions: true,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Plain alpha-beta with none of the selective techniques enabled.
+    pub fn none() -> Self {
+        SearchOptions {
+            null_move: false,
+            late_move_reductions: false,
+            futility: false,
+            reverse_futility: false,
+            aspiration_windows: false,
+            check_extensions: false,
+        }
+    }
+}
+
+/// Progress report sent to the info callback for every line of every completed iteration.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    /// Rank of the line, starting at 1.
+    pub multi_pv: usize,
+    pub depth: u32,
+    pub seldepth: u32,
+    pub score: i32,
+    pub nodes: u64,
+    pub time: Duration,
+    pub hashfull: usize,
+    pub pv: Vec<UserMove>,
+}
+
+impl SearchInfo {
+    pub fn nps(&self) -> u64 {
+        let millis = self.time.as_millis().max(1) as u64;
+        self.nodes * 1000 / millis
+    }
+
+    pub fn mate_in(&self) -> Option<i32> {
+        mate_distance(self.score)
+    }
+}
+
+pub type InfoCallback = Box<dyn FnMut(&SearchInfo) + Send>;
+
+/// One of the best root moves with its score and principal variation.
+#[derive(Clone, Debug)]
+pub struct PvLine {
+    pub score: i32,
+    pub depth: u32,
+    pub pv: Vec<UserMove>,
+    pub san: Vec<String>,
+}
+
+impl PvLine {
+    pub fn mate_in(&self) -> Option<i32> {
+        mate_distance(self.score)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: Option<UserMove>,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub pv: Vec<UserMove>,
+    /// Best first, as many as `SearchLimits::multi_pv` asked for and the position allows.
+    pub lines: Vec<PvLine>,
+}
+
+impl SearchResult {
+    /// Number of moves until mate, negative when the side to move is the one getting mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        mate_distance(self.score)
+    }
+}
+
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE_SCORE - MAX_PLY as i32
+}
+
+pub fn mate_distance(score: i32) -> Option<i32> {
+    if !is_mate_score(score) {
+        return None;
+    }
+    let plies = MATE_SCORE - score.abs();
+    let moves = (plies + 1) / 2;
+    if score > 0 {
+        Some(moves)
+    } else {
+        Some(-moves)
+    }
+}
+
+pub struct Searcher {
+    limits: SearchLimits,
+    options: SearchOptions,
+    start: Instant,
+    time_manager: Option<TimeManager>,
+    nodes: u64,
+    seldepth: usize,
+    stopped: bool,
+    stop: Arc<AtomicBool>,
+    ponder: Arc<AtomicBool>,
+    was_pondering: bool,
+    info_callback: Option<InfoCallback>,
+    pv_table: Vec<Vec<UserMove>>,
+    previous_pv: Vec<UserMove>,
+    path: Vec<Board>,
+    /// The game's positions before the root, back to its last capture or pawn move.
+    history: Vec<Board>,
+    tt: Arc<TranspositionTable>,
+    ordering: OrderingTables,
+    move_stack: Vec<Option<UserMove>>,
+    tablebase: Option<Arc<Tablebase>>,
+    excluded_root_moves: Vec<UserMove>,
+    /// Lazy SMP helpers, one per extra thread, searching the same position into the shared
+    /// transposition table.
+    helpers: Vec<Searcher>,
+    helpers_stop: Arc<AtomicBool>,
+    /// Nodes of all threads, to which each adds its own count every few nodes.
+    shared_nodes: Arc<AtomicU64>,
+    flushed_nodes: u64,
+}
+
+impl Searcher {
+    pub fn new(limits: SearchLimits) -> Self {
+        Self::with_table(limits, Arc::new(TranspositionTable::default()))
+    }
+
+    fn with_table(limits: SearchLimits, tt: Arc<TranspositionTable>) -> Self {
+        Searcher {
+            limits,
+            options: SearchOptions::default(),
+            start: Instant::now(),
+            time_manager: None,
+            nodes: 0,
+            seldepth: 0,
+            stopped: false,
+            stop: Arc::new(AtomicBool::new(false)),
+            ponder: Arc::new(AtomicBool::new(false)),
+            was_pondering: false,
+            info_callback: None,
+            pv_table: vec![Vec::new(); MAX_PLY + 1],
+            previous_pv: Vec::new(),
+            path: Vec::new(),
+            history: Vec::new(),
+            tt,
+            ordering: OrderingTables::new(),
+            move_stack: vec![None; MAX_PLY + 1],
+            tablebase: None,
+            excluded_root_moves: Vec::new(),
+            helpers: Vec::new(),
+            helpers_stop: Arc::new(AtomicBool::new(false)),
+            shared_nodes: Arc::new(AtomicU64::new(0)),
+            flushed_nodes: 0,
+        }
+    }
+
+    /// Number of threads to search with. One thread, the default, keeps the search
+    /// deterministic: the same position, limits and hash contents give the same result.
+    pub fn set_threads(&mut self, threads: usize) {
+        let helpers = threads.max(1) - 1;
+        self.helpers.truncate(helpers);
+        while self.helpers.len() < helpers {
+            let mut helper = Searcher::with_table(SearchLimits::default(), self.tt.clone());
+            helper.stop = self.helpers_stop.clone();
+            helper.shared_nodes = self.shared_nodes.clone();
+            self.helpers.push(helper);
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.helpers.len() + 1
+    }
+
+    pub fn set_limits(&mut self, limits: SearchLimits) {
+        self.limits = limits;
+    }
+
+    /// Flag that aborts the running search when set, e.g. from another thread.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// While this flag is set the search ignores its time limits; clearing it (a ponder hit)
+    /// restarts the clock.
+    pub fn ponder_flag(&self) -> Arc<AtomicBool> {
+        self.ponder.clone()
+    }
+
+    /// The positions of the game so far, oldest first and without the one to search, so that
+    /// going back to any of them counts as a repetition.
+    pub fn set_history(&mut self, history: &[Board]) {
+        let irreversible = history
+            .iter()
+            .rposition(|board| board.num_half_moves == 0)
+            .unwrap_or(0);
+        self.history = history[irreversible..].to_vec();
+    }
+
+    pub fn set_info_callback(&mut self, callback: Option<InfoCallback>) {
+        self.info_callback = callback;
+    }
+
+    /// Endgame tables the search probes below the root, scoring covered positions as exact
+    /// mates or draws.
+    pub fn set_tablebase(&mut self, tablebase: Option<Arc<Tablebase>>) {
+        self.tablebase = tablebase;
+    }
+
+    pub fn set_options(&mut self, options: SearchOptions) {
+        self.options = options;
+    }
+
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.tt = Arc::new(TranspositionTable::new(size_mb));
+    }
+
+    pub fn clear_hash(&mut self) {
+        self.tt.clear();
+        self.ordering.clear();
+        for helper in &mut self.helpers {
+            helper.ordering.clear();
+        }
+    }
+
+    pub fn tt_stats(&self) -> TtStats {
+        self.tt.stats()
+    }
+
+    pub fn search(&mut self, board: &Board) -> SearchResult {
+        self.tt.new_search();
+        self.shared_nodes.store(0, Ordering::Relaxed);
+        if self.helpers.is_empty() {
+            return self.iterate(board, 1);
+        }
+
+        // Helpers run without a clock until the main thread is done, each starting at a
+        // different depth so their searches fill the table with different subtrees.
+        self.helpers_stop.store(false, Ordering::Relaxed);
+        let helper_limits = SearchLimits {
+            depth: self.limits.depth,
+            nodes: self.limits.nodes,
+            mate: self.limits.mate,
+            search_moves: self.limits.search_moves.clone(),
+            ..Default::default()
+        };
+        let mut helpers = std::mem::take(&mut self.helpers);
+        for helper in &mut helpers {
+            helper.limits = helper_limits.clone();
+            helper.options = self.options;
+            helper.tt = self.tt.clone();
+            helper.tablebase = self.tablebase.clone();
+            helper.history = self.history.clone();
+        }
+        let result = thread::scope(|scope| {
+            for (index, helper) in helpers.iter_mut().enumerate() {
+                scope.spawn(move || helper.iterate(board, 1 + (index as u32 + 1) % 2));
+            }
+            let result = self.iterate(board, 1);
+            self.helpers_stop.store(true, Ordering::Relaxed);
+            result
+        });
+        self.helpers = helpers;
+        SearchResult {
+            nodes: self.shared_nodes.load(Ordering::Relaxed),
+            ..result
+        }
+    }
+
+    /// Iterative deepening from `first_depth`, the whole search of a single thread.
+    fn iterate(&mut self, board: &Board, first_depth: u32) -> SearchResult {
+        self.start = Instant::now();
+        self.time_manager = self.limits.clock.as_ref().map(TimeManager::new);
+        self.was_pondering = self.ponder.load(Ordering::Relaxed);
+        self.nodes = 0;
+        self.flushed_nodes = 0;
+        self.stopped = false;
+        self.previous_pv.clear();
+        self.ordering.age();
+
+        self.excluded_root_moves.clear();
+        let root_moves: Vec<UserMove> = board
+            .legal_moves()
+            .iter()
+            .map(|m| m.piece_move)
+            .filter(|m| self.is_root_move_allowed(m))
+            .collect();
+        let multi_pv = self.limits.multi_pv.clamp(1, root_moves.len().max(1));
+        let mut result = SearchResult {
+            best_move: root_moves.first().copied(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            pv: Vec::new(),
+            lines: Vec::new(),
+        };
+        let max_depth = match self.limits.mate {
+            Some(mate) => self.limits.depth.unwrap_or(u32::MAX).min(mate * 2 + 2),
+            None => self.limits.depth.unwrap_or(MAX_PLY as u32),
+        };
+        for depth in first_depth..=max_depth.min(MAX_PLY as u32) {
+            self.seldepth = 0;
+            let Some(lines) = self.search_lines(board, depth, multi_pv, &result.lines) else {
+                break;
+            };
+            let best = &lines[0];
+            result = SearchResult {
+                best_move: best.pv.first().copied().or(result.best_move),
+                score: best.score,
+                depth,
+                nodes: self.searched_nodes(),
+                pv: best.pv.clone(),
+                lines,
+            };
+            for (index, line) in result.lines.iter().enumerate() {
+                self.report(index + 1, line);
+            }
+            let score = result.score;
+            let mate = mate_distance(score);
+            if mate.is_some_and(|moves| moves.unsigned_abs() * 2 <= depth) {
+                break;
+            }
+            if let (Some(limit), Some(moves)) = (self.limits.mate, mate) {
+                if moves > 0 && moves as u32 <= limit {
+                    break;
+                }
+            }
+            if let Some(time_manager) = &mut self.time_manager {
+                time_manager.update(result.best_move, score);
+                if !self.ponder.load(Ordering::Relaxed)
+                    && time_manager.should_stop_iterating(self.start.elapsed())
+                {
+                    break;
+                }
+            }
+        }
+        self.flush_nodes();
+        result.nodes = self.searched_nodes();
+        result
+    }
+
+    fn flush_nodes(&mut self) {
+        self.shared_nodes
+            .fetch_add(self.nodes - self.flushed_nodes, Ordering::Relaxed);
+        self.flushed_nodes = self.nodes;
+    }
+
+    /// Nodes searched so far by every thread, exact for this one and up to a clock check
+    /// behind for the others.
+    fn searched_nodes(&self) -> u64 {
+        self.shared_nodes.load(Ordering::Relaxed) + self.nodes - self.flushed_nodes
+    }
+
+    /// One iteration of up to `count` lines, each searched with the root moves of the lines
+    /// before it excluded. Returns the lines best first, or `None` when the search was stopped
+    /// before finishing them.
+    fn search_lines(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        count: usize,
+        previous: &[PvLine],
+    ) -> Option<Vec<PvLine>> {
+        self.excluded_root_moves.clear();
+        let mut lines: Vec<PvLine> = Vec::new();
+        for index in 0..count {
+            let previous_line = previous.get(index);
+            self.previous_pv = previous_line.map_or(Vec::new(), |line| line.pv.clone());
+            let previous_score = previous_line.map_or(0, |line| line.score);
+            let score = self.aspiration_search(board, depth, previous_score);
+            if self.stopped {
+                return None;
+            }
+            let pv = self.pv_table[0].clone();
+            let Some(first) = pv.first() else {
+                break;
+            };
+            self.excluded_root_moves.push(*first);
+            lines.push(PvLine {
+                score,
+                depth,
+                san: pv_to_san(board, &pv),
+                pv,
+            });
+        }
+        self.excluded_root_moves.clear();
+        lines.sort_by_key(|line| -line.score);
+        match lines.is_empty() {
+            true => None,
+            false => Some(lines),
+        }
+    }
+
+    fn report(&mut self, multi_pv: usize, line: &PvLine) {
+        let hashfull = self.tt.stats().hashfull;
+        let nodes = self.searched_nodes();
+        if let Some(callback) = &mut self.info_callback {
+            callback(&SearchInfo {
+                multi_pv,
+                depth: line.depth,
+                seldepth: self.seldepth as u32,
+                score: line.score,
+                nodes,
+                time: self.start.elapsed(),
+                hashfull,
+                pv: line.pv.clone(),
+            });
+        }
+    }
+
+    fn is_root_move_allowed(&self, m: &UserMove) -> bool {
+        (self.limits.search_moves.is_empty() || self.limits.search_moves.contains(m))
+            && !self.excluded_root_moves.contains(m)
+    }
+
+    fn aspiration_search(&mut self, board: &Board, depth: u32, previous_score: i32) -> i32 {
+        if !self.options.aspiration_windows
+            || depth < ASPIRATION_MIN_DEPTH
+            || is_mate_score(previous_score)
+        {
+            self.path.clear();
+            return self.negamax(board, depth, 0, -INFINITE_SCORE, INFINITE_SCORE);
+        }
+        let mut delta = ASPIRATION_WINDOW;
+        let mut alpha = (previous_score - delta).max(-INFINITE_SCORE);
+        let mut beta = (previous_score + delta).min(INFINITE_SCORE);
+        loop {
+            self.path.clear();
+            let score = self.negamax(board, depth, 0, alpha, beta);
+            if self.stopped {
+                return score;
+            }
+            if score <= alpha {
+                alpha = (score - delta).max(-INFINITE_SCORE);
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITE_SCORE);
+            } else {
+                return score;
+            }
+            delta *= 2;
+            if delta > ASPIRATION_WINDOW * 16 {
+                alpha = -INFINITE_SCORE;
+                beta = INFINITE_SCORE;
+            }
+        }
+    }
+
+    /// Tables know nothing of en passant or the fifty-move rule, so positions where either
+    /// matters are left to the search.
+    fn probe_tablebase(&self, board: &Board, ply: usize) -> Option<i32> {
+        let tablebase = self.tablebase.as_ref()?;
+        if board.en_passant_square.is_some() || board.num_half_moves >= 100 {
+            return None;
+        }
+        let probe = tablebase.probe(board)?;
+        let plies = ply as i32 + probe.dtm.unwrap_or(0) as i32;
+        match probe.wdl {
+            Wdl::Win => Some(MATE_SCORE - plies),
+            Wdl::Loss => Some(-(MATE_SCORE - plies)),
+            Wdl::Draw => Some(0),
+        }
+    }
+
+    fn negamax(&mut self, board: &Board, depth: u32, ply: usize, alpha: i32, beta: i32) -> i32 {
+        if depth == 0 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+        self.nodes += 1;
+        self.pv_table[ply].clear();
+        if self.should_stop() {
+            self.stopped = true;
+            return 0;
+        }
+        if ply > 0 && self.is_repetition(board) {
+            return 0;
+        }
+        if ply > 0 {
+            if let Some(score) = self.probe_tablebase(board, ply) {
+                return score;
+            }
+        }
+
+        let pv_node = beta - alpha > 1;
+        let key = board.hash();
+        let mut tt_move = None;
+        if let Some(entry) = self.tt.probe(key, ply) {
+            tt_move = entry.best_move;
+            if ply > 0 && !pv_node && entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return entry.score,
+                    Bound::Lower if entry.score >= beta => return entry.score,
+                    Bound::Upper if entry.score <= alpha => return entry.score,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut picker = if board.variant == Variant::Standard {
+            if board.num_half_moves >= 100 {
+                return 0;
+            }
+            self.move_picker(None, ply, tt_move)
+        } else {
+            let moves = board.legal_moves();
+            if let Some(score) = terminal_score(board, &moves, ply) {
+                return score;
+            }
+            self.move_picker(Some(moves), ply, tt_move)
+        };
+        if ply >= MAX_PLY {
+            return eval::evaluate(board);
+        }
+
+        let alpha = alpha.max(-(MATE_SCORE - ply as i32));
+        let beta = beta.min(MATE_SCORE - ply as i32 - 1);
+        if alpha >= beta {
+            return alpha;
+        }
+
+        let in_check = is_checked(board);
+        let depth = if in_check && self.options.check_extensions {
+            depth + 1
+        } else {
+            depth
+        };
+        let static_eval = if in_check {
+            -INFINITE_SCORE
+        } else {
+            eval::evaluate(board)
+        };
+
+        if !pv_node && !in_check && ply > 0 && !is_mate_score(beta) {
+            if self.options.reverse_futility
+                && depth <= REVERSE_FUTILITY_DEPTH
+                && static_eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta
+            {
+                return static_eval;
+            }
+
+            let previous_was_null = self.move_stack[ply - 1].is_none();
+            if self.options.null_move
+                && depth >= NULL_MOVE_MIN_DEPTH
+                && !previous_was_null
+                && static_eval >= beta
+                && board.variant == Variant::Standard
+                && has_non_pawn_material(board)
+            {
+                let reduction = NULL_MOVE_REDUCTION + depth / 6;
+                let mut child = *board;
+                child.apply_null_move();
+                self.move_stack[ply] = None;
+                self.path.push(*board);
+                let score = -self.negamax(
+                    &child,
+                    depth.saturating_sub(1 + reduction),
+                    ply + 1,
+                    -beta,
+                    -beta + 1,
+                );
+                self.path.pop();
+                if self.stopped {
+                    return 0;
+                }
+                if score >= beta {
+                    return if is_mate_score(score) { beta } else { score };
+                }
+            }
+        }
+        let futility_pruning = self.options.futility
+            && !pv_node
+            && !in_check
+            && depth <= FUTILITY_DEPTH
+            && static_eval + FUTILITY_MARGIN * depth as i32 <= alpha;
+
+        let original_alpha = alpha;
+        let mut alpha = alpha;
+        let mut best_score = -INFINITE_SCORE;
+        let mut best_move = None;
+        let mut tried_quiets: Vec<UserMove> = Vec::new();
+        let mut moves_searched = 0;
+        self.path.push(*board);
+        while let Some(m) = picker.next(board, &self.ordering) {
+            if ply == 0 && !self.is_root_move_allowed(&m.piece_move) {
+                continue;
+            }
+            let quiet = !is_tactical(&m);
+            let mut child = *board;
+            child.apply_move(&m);
+            let gives_check = is_checked(&child);
+
+            if futility_pruning && quiet && !gives_check && moves_searched > 0 {
+                tried_quiets.push(m.piece_move);
+                continue;
+            }
+
+            self.move_stack[ply] = Some(m.piece_move);
+            let score = if moves_searched == 0 {
+                -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha)
+            } else {
+                let reduction = if self.options.late_move_reductions
+                    && depth >= LMR_MIN_DEPTH
+                    && moves_searched >= LMR_MIN_MOVES
+                    && quiet
+                    && !in_check
+                    && !gives_check
+                {
+                    late_move_reduction(depth, moves_searched, pv_node)
+                } else {
+                    0
+                };
+                let mut score = -self.negamax(
+                    &child,
+                    (depth - 1).saturating_sub(reduction),
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                );
+                if score > alpha && reduction > 0 {
+                    score = -self.negamax(&child, depth - 1, ply + 1, -alpha - 1, -alpha);
+                }
+                if score > alpha && score < beta {
+                    score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha);
+                }
+                score
+            };
+            moves_searched += 1;
+            if self.stopped {
+                self.path.pop();
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(m.piece_move);
+            }
+            if score > alpha {
+                alpha = score;
+                let mut pv = vec![m.piece_move];
+                pv.extend_from_slice(&self.pv_table[ply + 1]);
+                self.pv_table[ply] = pv;
+            }
+            if alpha >= beta {
+                if quiet {
+                    let previous = ply.checked_sub(1).and_then(|ply| self.move_stack[ply]);
+                    self.ordering.record_cutoff(
+                        board.player_turn,
+                        ply,
+                        depth,
+                        m.piece_move,
+                        previous,
+                        &tried_quiets,
+                    );
+                }
+                break;
+            }
+            if quiet {
+                tried_quiets.push(m.piece_move);
+            }
+        }
+        self.path.pop();
+
+        if best_move.is_none() {
+            return if in_check {
+                -(MATE_SCORE - ply as i32)
+            } else {
+                0
+            };
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.tt.store(key, depth, bound, best_score, best_move, ply);
+        best_score
+    }
+
+    fn move_picker(
+        &self,
+        moves: Option<Vec<Move>>,
+        ply: usize,
+        tt_move: Option<UserMove>,
+    ) -> MovePicker {
+        let hash_move = tt_move.or_else(|| self.previous_pv.get(ply).copied());
+        let killers = self.ordering.killers(ply);
+        let previous = ply.checked_sub(1).and_then(|ply| self.move_stack[ply]);
+        let counter_move = self.ordering.counter_move(previous);
+        match moves {
+            Some(moves) => MovePicker::with_moves(moves, hash_move, killers, counter_move),
+            None => MovePicker::new(hash_move, killers, counter_move),
+        }
+    }
+
+    fn quiescence(&mut self, board: &Board, ply: usize, alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        self.pv_table[ply].clear();
+        if self.should_stop() {
+            self.stopped = true;
+            return 0;
+        }
+
+        let in_check = is_checked(board);
+        let mut moves = if in_check || board.variant != Variant::Standard {
+            let moves = board.legal_moves();
+            if let Some(score) = terminal_score(board, &moves, ply) {
+                return score;
+            }
+            moves
+        } else {
+            board.tactical_moves()
+        };
+        let stand_pat = eval::evaluate(board);
+        if ply >= MAX_PLY {
+            return stand_pat;
+        }
+
+        let mut alpha = alpha;
+        let mut best_score = -INFINITE_SCORE;
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            best_score = stand_pat;
+            moves.retain(is_tactical);
+        }
+        moves.sort_by_key(|m| -mvv_lva(board, m));
+
+        for m in &moves {
+            if !in_check {
+                if stand_pat + material_gain(m) + DELTA_MARGIN < alpha {
+                    continue;
+                }
+                if see::static_exchange(board, &m.piece_move) < 0 {
+                    continue;
+                }
+            }
+            let mut child = *board;
+            child.apply_move(m);
+            let score = -self.quiescence(&child, ply + 1, -beta, -alpha);
+            if self.stopped {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+
+    fn is_repetition(&self, board: &Board) -> bool {
+        let played = self.history.iter().chain(self.path.iter());
+        played.rev().skip(1).step_by(2).any(|previous| {
+            previous.board == board.board
+                && previous.player_turn == board.player_turn
+                && previous.en_passant_square == board.en_passant_square
+        })
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped || self.stop.load(Ordering::Relaxed) {
+            return true;
+        }
+        if self
+            .limits
+            .nodes
+            .is_some_and(|nodes| self.searched_nodes() >= nodes)
+        {
+            return true;
+        }
+        if self.nodes.is_multiple_of(NODES_BETWEEN_CLOCK_CHECKS) {
+            self.flush_nodes();
+            if self.ponder.load(Ordering::Relaxed) {
+                return false;
+            }
+            if self.was_pondering {
+                self.was_pondering = false;
+                self.start = Instant::now();
+            }
+            let elapsed = self.start.elapsed();
+            if self.limits.time.is_some_and(|time| elapsed >= time) {
+                return true;
+            }
+            if let Some(time_manager) = &self.time_manager {
+                return elapsed >= time_manager.hard_limit();
+            }
+        }
+        false
+    }
+}
+
+/// The principal variation in SAN, cut short at the first move that is not legal, which a
+/// line pulled from a stale transposition table entry can end with.
+pub fn pv_to_san(board: &Board, pv: &[UserMove]) -> Vec<String> {
+    let mut board = *board;
+    let mut san = Vec::new();
+    for m in pv {
+        match board.to_san(m) {
+            Ok(text) => san.push(text),
+            Err(_) => break,
+        }
+        board.make_move(m);
+    }
+    san
+}
+
+fn terminal_score(board: &Board, moves: &[Move], ply: usize) -> Option<i32> {
+    match board.game_status_with_moves(moves) {
+        GameStatus::Ongoing => None,
+        GameStatus::Draw => Some(0),
+        GameStatus::Checkmate(winner) | GameStatus::VariantWin(winner) => {
+            if winner == board.player_turn {
+                Some(MATE_SCORE - ply as i32)
+            } else {
+                Some(-(MATE_SCORE - ply as i32))
+            }
+        }
+    }
+}
+
+fn has_non_pawn_material(board: &Board) -> bool {
+    board.board.iter().flatten().flatten().any(|piece| {
+        piece.color == board.player_turn && !matches!(piece.kind, PieceKind::Pawn | PieceKind::King)
+    })
+}
+
+fn late_move_reduction(depth: u32, moves_searched: usize, pv_node: bool) -> u32 {
+    let reduction = 0.75 + (depth as f64).ln() * (moves_searched as f64).ln() / 2.25;
+    let reduction = reduction as u32 - u32::from(pv_node && reduction >= 2.0);
+    reduction.min(depth - 2)
+}
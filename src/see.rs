@@ -0,0 +1,140 @@
+use crate::{models::*, moves::is_square_within_bounds, Board, UserMove};
+
+pub fn piece_value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Pawn => 100,
+        PieceKind::Knight => 320,
+        PieceKind::Bishop => 330,
+        PieceKind::Rook => 500,
+        PieceKind::Queen => 900,
+        PieceKind::King => 20_000,
+    }
+}
+
+/// Static exchange evaluation: the material balance, in centipawns, of playing `user_move` and
+/// then letting both sides keep recapturing on the destination square with their least valuable
+/// piece for as long as it pays off.
+pub fn static_exchange(board: &Board, user_move: &UserMove) -> i32 {
+    let (origin, target) = (user_move.origin, user_move.destination);
+    let mut squares = board.board;
+    let mover = match squares[origin.0][origin.1] {
+        Some(piece) => piece,
+        None => return 0,
+    };
+
+    let mut gains: Vec<i32> = Vec::with_capacity(32);
+    let mut first_gain = match squares[target.0][target.1] {
+        Some(piece) => piece_value(piece.kind),
+        None if mover.kind == PieceKind::Pawn && board.en_passant_square == Some(target) => {
+            squares[origin.0][target.1] = None;
+            piece_value(PieceKind::Pawn)
+        }
+        None => 0,
+    };
+    let mut attacker_value = piece_value(mover.kind);
+    if let Some(promotion) = user_move.promotion_request {
+        first_gain += piece_value(promotion) - piece_value(PieceKind::Pawn);
+        attacker_value = piece_value(promotion);
+    }
+    gains.push(first_gain);
+    squares[origin.0][origin.1] = None;
+
+    let mut side = mover.color.get_opposite_color();
+    while let Some((square, kind)) = least_valuable_attacker(&squares, target, side) {
+        let previous_gain = *gains.last().unwrap();
+        gains.push(attacker_value - previous_gain);
+        attacker_value = piece_value(kind);
+        squares[square.0][square.1] = None;
+        side = side.get_opposite_color();
+    }
+
+    while gains.len() > 1 {
+        let last = gains.pop().unwrap();
+        let previous = gains.last_mut().unwrap();
+        *previous = -(-*previous).max(last);
+    }
+    gains[0]
+}
+
+fn least_valuable_attacker(
+    squares: &[[Option<Piece>; 8]; 8],
+    target: (usize, usize),
+    color: Color,
+) -> Option<((usize, usize), PieceKind)> {
+    let piece_at = |offset: (isize, isize)| {
+        let square = (target.0 as isize + offset.0, target.1 as isize + offset.1);
+        if !is_square_within_bounds(square) {
+            return None;
+        }
+        let square = (square.0 as usize, square.1 as usize);
+        squares[square.0][square.1]
+            .filter(|piece| piece.color == color)
+            .map(|piece| (square, piece.kind))
+    };
+    let slider_at = |direction: (isize, isize)| {
+        let mut square = (target.0 as isize, target.1 as isize);
+        loop {
+            square = (square.0 + direction.0, square.1 + direction.1);
+            if !is_square_within_bounds(square) {
+                return None;
+            }
+            if let Some(piece) = squares[square.0 as usize][square.1 as usize] {
+                return (piece.color == color)
+                    .then_some(((square.0 as usize, square.1 as usize), piece.kind));
+            }
+        }
+    };
+
+    let pawn_row_offset = match color {
+        Color::White => -1,
+        Color::Black => 1,
+    };
+    let knight_offsets = [
+        (2, 1),
+        (2, -1),
+        (-2, 1),
+        (-2, -1),
+        (1, 2),
+        (1, -2),
+        (-1, 2),
+        (-1, -2),
+    ];
+    let diagonals = [(-1, -1), (-1, 1), (1, 1), (1, -1)];
+    let lines = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+    let mut candidates: Vec<((usize, usize), PieceKind)> = Vec::new();
+    candidates.extend(
+        [(pawn_row_offset, -1), (pawn_row_offset, 1)]
+            .into_iter()
+            .filter_map(piece_at)
+            .filter(|(_, kind)| *kind == PieceKind::Pawn),
+    );
+    candidates.extend(
+        knight_offsets
+            .into_iter()
+            .filter_map(piece_at)
+            .filter(|(_, kind)| *kind == PieceKind::Knight),
+    );
+    candidates.extend(
+        diagonals
+            .into_iter()
+            .filter_map(slider_at)
+            .filter(|(_, kind)| matches!(kind, PieceKind::Bishop | PieceKind::Queen)),
+    );
+    candidates.extend(
+        lines
+            .into_iter()
+            .filter_map(slider_at)
+            .filter(|(_, kind)| matches!(kind, PieceKind::Rook | PieceKind::Queen)),
+    );
+    candidates.extend(
+        diagonals
+            .into_iter()
+            .chain(lines)
+            .filter_map(piece_at)
+            .filter(|(_, kind)| *kind == PieceKind::King),
+    );
+    candidates
+        .into_iter()
+        .min_by_key(|(_, kind)| piece_value(*kind))
+}
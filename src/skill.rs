@@ -0,0 +1,130 @@
+use crate::{
+    rng::Rng,
+    search::{is_mate_score, PvLine, SearchLimits, SearchResult},
+};
+
+pub const MAX_SKILL_LEVEL: u32 = 20;
+pub const MIN_ELO: u32 = 600;
+pub const MAX_ELO: u32 = 2400;
+
+/// Root moves weighed at reduced strength, so there is a choice beyond the best move.
+const CANDIDATE_LINES: usize = 4;
+/// A blunder gives up at least this much compared with the best move.
+const BLUNDER_MARGIN: i32 = 150;
+const PAWN_VALUE: i32 = 100;
+
+/// Playing strength from 0 to 20. Below 20 the engine searches shallower and with fewer nodes,
+/// picks among the best few root moves with a bias towards the best that weakens with the level,
+/// and now and then plays a clearly worse move.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Skill {
+    pub level: u32,
+}
+
+impl Skill {
+    pub fn full_strength() -> Self {
+        Skill {
+            level: MAX_SKILL_LEVEL,
+        }
+    }
+
+    pub fn from_level(level: u32) -> Self {
+        Skill {
+            level: level.min(MAX_SKILL_LEVEL),
+        }
+    }
+
+    /// A level for a target Elo between `MIN_ELO` and `MAX_ELO`, spread evenly over the levels.
+    /// The scale is a rough guide and has not been calibrated against rated play.
+    pub fn from_elo(elo: u32) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        Self::from_level((elo - MIN_ELO) * MAX_SKILL_LEVEL / (MAX_ELO - MIN_ELO))
+    }
+
+    pub fn is_full_strength(&self) -> bool {
+        self.level >= MAX_SKILL_LEVEL
+    }
+
+    /// `limits` tightened for this level, with enough lines searched to choose from.
+    pub fn limits(&self, limits: SearchLimits) -> SearchLimits {
+        if self.is_full_strength() {
+            return limits;
+        }
+        let depth = 1 + self.level / 2;
+        let nodes = 500u64 << (self.level / 2);
+        SearchLimits {
+            depth: Some(limits.depth.map_or(depth, |limit| limit.min(depth))),
+            nodes: Some(limits.nodes.map_or(nodes, |limit| limit.min(nodes))),
+            multi_pv: limits.multi_pv.max(CANDIDATE_LINES),
+            ..limits
+        }
+    }
+
+    /// Chance of a blunder on any one move, from 20% at level 0 down to nothing at level 20.
+    pub fn blunder_chance(&self) -> f64 {
+        let weakness = (MAX_SKILL_LEVEL - self.level.min(MAX_SKILL_LEVEL)) as f64;
+        weakness * weakness / 2000.0
+    }
+
+    /// Picks the move to play from a search made with `limits`, returning the result with that
+    /// move's line as its best move, score and principal variation. The same `rng` state gives
+    /// the same choice.
+    pub fn choose(&self, result: SearchResult, rng: &mut Rng) -> SearchResult {
+        if self.is_full_strength() || result.lines.len() < 2 {
+            return result;
+        }
+        let line = match self.blunder(&result.lines, rng) {
+            Some(line) => line,
+            None => self.pick_near_best(&result.lines, rng),
+        }
+        .clone();
+        SearchResult {
+            best_move: line.pv.first().copied().or(result.best_move),
+            score: line.score,
+            pv: line.pv,
+            ..result
+        }
+    }
+
+    /// Scores every line by how far it trails the best one, plus a random bonus that grows as
+    /// the level drops, and keeps the highest.
+    fn pick_near_best<'a>(&self, lines: &'a [PvLine], rng: &mut Rng) -> &'a PvLine {
+        let top = lines[0].score;
+        let spread = (top - lines[lines.len() - 1].score).min(PAWN_VALUE);
+        let weakness = 120 - 2 * self.level as i32;
+        let mut best = &lines[0];
+        let mut best_value = i32::MIN;
+        for line in lines {
+            let bonus = rng.below(weakness as u64) as i32;
+            let value = line.score + (weakness * (top - line.score) + spread * bonus) / 128;
+            if value > best_value {
+                best = line;
+                best_value = value;
+            }
+        }
+        best
+    }
+
+    /// A human-looking mistake: a move that gives up real material or position, but not one
+    /// that walks into a forced mate.
+    fn blunder<'a>(&self, lines: &'a [PvLine], rng: &mut Rng) -> Option<&'a PvLine> {
+        if rng.next_f64() >= self.blunder_chance() {
+            return None;
+        }
+        let top = lines[0].score;
+        let mistakes: Vec<&PvLine> = lines
+            .iter()
+            .filter(|line| top - line.score >= BLUNDER_MARGIN && !is_mate_score(line.score))
+            .collect();
+        match mistakes.len() {
+            0 => None,
+            count => Some(mistakes[rng.below(count as u64) as usize]),
+        }
+    }
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Self::full_strength()
+    }
+}
@@ -0,0 +1,418 @@
+use crate::{
+    helpers::{get_king_square, is_checked},
+    models::*,
+    Board, UserMove,
+};
+
+/// Piece artwork is drawn on a square this many units wide.
+const ARTWORK_SIZE: f64 = 45.0;
+pub const DEFAULT_LIGHT_COLOR: &str = "#f0d9b5";
+pub const DEFAULT_DARK_COLOR: &str = "#b58863";
+pub const DEFAULT_HIGHLIGHT_COLOR: &str = "#14a0e6";
+pub const DEFAULT_ARROW_COLOR: &str = "#15781b";
+const LAST_MOVE_COLOR: &str = "#cdd26a";
+const HIGHLIGHT_OPACITY: f64 = 0.5;
+const ARROW_OPACITY: f64 = 0.8;
+
+/// One part of a piece's artwork, in a 45 by 45 square.
+enum Shape {
+    /// Filled with the piece's colour and outlined.
+    Body(&'static str),
+    Ball(f64, f64, f64),
+    /// An outline only, such as the king's cross.
+    Line(&'static str),
+    /// Inner lines in the opposite colour, so that black pieces keep their shape.
+    Detail(&'static str),
+    Eye(f64, f64, f64),
+}
+
+fn artwork(kind: PieceKind) -> &'static [Shape] {
+    match kind {
+        PieceKind::King => &[
+            Shape::Line("M 22.5 5 L 22.5 11.5 M 19.5 8 L 25.5 8"),
+            Shape::Body("M 22.5 23 C 21 18.5 20.5 15 22.5 12.5 C 24.5 15 24 18.5 22.5 23 Z"),
+            Shape::Body(
+                "M 12 36 C 10 31 7 27 7.5 22.5 C 8 18 12 15.5 16 16.5 C 19 17.3 21.5 20 22.5 23 \
+                 C 23.5 20 26 17.3 29 16.5 C 33 15.5 37 18 37.5 22.5 C 38 27 35 31 33 36 Z",
+            ),
+            Shape::Body("M 11 39.5 L 34 39.5 L 34 36 L 11 36 Z"),
+            Shape::Detail("M 12.5 32 L 32.5 32"),
+        ],
+        PieceKind::Queen => &[
+            Shape::Body("M 10 39.5 L 35 39.5 L 35 36 L 10 36 Z"),
+            Shape::Body(
+                "M 11 36 L 8.5 14.5 L 15 27 L 15.5 12 L 20.5 26 L 22.5 10.5 L 24.5 26 \
+                 L 29.5 12 L 30 27 L 36.5 14.5 L 34 36 Z",
+            ),
+            Shape::Ball(8.5, 12.5, 2.2),
+            Shape::Ball(15.5, 10.0, 2.2),
+            Shape::Ball(22.5, 8.5, 2.2),
+            Shape::Ball(29.5, 10.0, 2.2),
+            Shape::Ball(36.5, 12.5, 2.2),
+            Shape::Detail("M 11.5 32 L 33.5 32"),
+        ],
+        PieceKind::Rook => &[
+            Shape::Body("M 9 39 L 36 39 L 36 36 L 9 36 Z"),
+            Shape::Body("M 12 36 L 12 32 L 33 32 L 33 36 Z"),
+            Shape::Body("M 12 32 L 14 29.5 L 31 29.5 L 33 32 Z"),
+            Shape::Body("M 14 29.5 L 14 16.5 L 31 16.5 L 31 29.5 Z"),
+            Shape::Body("M 14 16.5 L 11 14 L 34 14 L 31 16.5 Z"),
+            Shape::Body(
+                "M 11 14 L 11 9 L 15 9 L 15 11 L 20 11 L 20 9 L 25 9 L 25 11 L 30 11 L 30 9 \
+                 L 34 9 L 34 14 Z",
+            ),
+            Shape::Detail(
+                "M 12.5 32 L 32.5 32 M 14.5 29.5 L 30.5 29.5 M 14.5 16.5 L 30.5 16.5 \
+                 M 11.5 14 L 33.5 14",
+            ),
+        ],
+        PieceKind::Bishop => &[
+            Shape::Body("M 9 39.5 L 36 39.5 L 36 36.5 L 9 36.5 Z"),
+            Shape::Body(
+                "M 15 36.5 C 15 33 17 31 19 30 C 15 27.5 13.5 24 13.5 21 C 13.5 16.5 17.5 12.5 \
+                 22.5 10 C 27.5 12.5 31.5 16.5 31.5 21 C 31.5 24 30 27.5 26 30 C 28 31 30 33 \
+                 30 36.5 Z",
+            ),
+            Shape::Ball(22.5, 8.0, 2.5),
+            Shape::Detail("M 22.5 15 L 22.5 23 M 18.5 19 L 26.5 19 M 19 30 L 26 30"),
+        ],
+        PieceKind::Knight => &[
+            Shape::Body(
+                "M 13 38.5 L 35 38.5 C 35 28 33 19 27 13 C 24 10 21 9 19 9 L 17 6 L 15.5 10 \
+                 C 12 12 10 16 8 21 C 7 23 8 25 10 25 C 12 25 14 23.5 17 22.5 C 18.5 22 20 22.5 \
+                 20 24 C 20 27 14 30 13 38.5 Z",
+            ),
+            Shape::Eye(15.5, 15.0, 1.2),
+        ],
+        PieceKind::Pawn => &[Shape::Body(
+            "M 22.5 9 C 19.7 9 17.5 11.2 17.5 14 C 17.5 15.6 18.2 17 19.4 17.9 C 17.1 19.2 \
+             15.5 21.6 15.5 24.4 C 15.5 26.5 16.5 28.4 18.1 29.6 C 14.9 31 12.4 34.3 12 38.5 \
+             L 33 38.5 C 32.6 34.3 30.1 31 26.9 29.6 C 28.5 28.4 29.5 26.5 29.5 24.4 C 29.5 \
+             21.6 27.9 19.2 25.6 17.9 C 26.8 17 27.5 15.6 27.5 14 C 27.5 11.2 25.3 9 22.5 9 Z",
+        )],
+    }
+}
+
+/// A square tinted with a colour.
+#[derive(Clone, Debug)]
+pub struct SquareHighlight {
+    pub square: (usize, usize),
+    pub color: String,
+}
+
+impl SquareHighlight {
+    pub fn new(square: (usize, usize)) -> Self {
+        SquareHighlight {
+            square,
+            color: DEFAULT_HIGHLIGHT_COLOR.to_owned(),
+        }
+    }
+}
+
+/// An arrow between the centres of two squares. An arrow from a square to itself is drawn as a
+/// ring.
+#[derive(Clone, Debug)]
+pub struct Arrow {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    pub color: String,
+}
+
+impl Arrow {
+    pub fn new(from: (usize, usize), to: (usize, usize)) -> Self {
+        Arrow {
+            from,
+            to,
+            color: DEFAULT_ARROW_COLOR.to_owned(),
+        }
+    }
+}
+
+/// How to draw a board as SVG. Squares are `(row, column)` like `UserMove`; colours are any SVG
+/// colour such as `#ff0000` or `red`.
+#[derive(Clone, Debug)]
+pub struct SvgOptions {
+    /// Width of one square in pixels.
+    pub square_size: u32,
+    /// The side shown at the bottom.
+    pub orientation: Color,
+    /// File letters and rank numbers in a margin around the board.
+    pub coordinates: bool,
+    pub light_color: String,
+    pub dark_color: String,
+    /// Tints the squares the move just played came from and went to.
+    pub last_move: Option<UserMove>,
+    /// Shades the king of the side to move when it is in check.
+    pub check: bool,
+    pub highlights: Vec<SquareHighlight>,
+    pub arrows: Vec<Arrow>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            square_size: 45,
+            orientation: Color::White,
+            coordinates: true,
+            light_color: DEFAULT_LIGHT_COLOR.to_owned(),
+            dark_color: DEFAULT_DARK_COLOR.to_owned(),
+            last_move: None,
+            check: true,
+            highlights: Vec::new(),
+            arrows: Vec::new(),
+        }
+    }
+}
+
+/// Draws `board` as a self-contained SVG document, with the artwork of every piece on the board
+/// embedded once and reused.
+pub fn to_svg(board: &Board, options: &SvgOptions) -> String {
+    let square = options.square_size.max(1) as f64;
+    let margin = match options.coordinates {
+        true => (square / 2.0).round(),
+        false => 0.0,
+    };
+    let size = 8.0 * square + 2.0 * margin;
+    // Top-left corner of a square in the drawing.
+    let corner = |(row, column): (usize, usize)| {
+        let (x, y) = match options.orientation {
+            Color::White => (column, 7 - row),
+            Color::Black => (7 - column, row),
+        };
+        (margin + x as f64 * square, margin + y as f64 * square)
+    };
+    let checked_king = match options.check && is_checked(board) {
+        true => get_king_square(board.board, &board.player_turn),
+        false => None,
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+         version=\"1.1\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n",
+        number(size)
+    );
+    svg.push_str("<defs>\n");
+    let mut defined: Vec<Piece> = Vec::new();
+    for piece in board.board.iter().flatten().flatten() {
+        if !defined.contains(piece) {
+            defined.push(*piece);
+            svg.push_str(&piece_definition(piece));
+        }
+    }
+    if checked_king.is_some() {
+        svg.push_str(
+            "<radialGradient id=\"check\"><stop offset=\"0%\" stop-color=\"#ff0000\"/>\
+             <stop offset=\"50%\" stop-color=\"#e70000\"/>\
+             <stop offset=\"100%\" stop-color=\"#9e0000\" stop-opacity=\"0\"/></radialGradient>\n",
+        );
+    }
+    svg.push_str("</defs>\n");
+
+    if options.coordinates {
+        svg.push_str(&format!(
+            "<rect x=\"0\" y=\"0\" width=\"{0}\" height=\"{0}\" fill=\"#212121\"/>\n",
+            number(size)
+        ));
+    }
+    for row in 0..8 {
+        for column in 0..8 {
+            let (x, y) = corner((row, column));
+            let color = match (row + column) % 2 {
+                0 => &options.dark_color,
+                _ => &options.light_color,
+            };
+            svg.push_str(&rect(x, y, square, &format!("fill=\"{}\"", escape(color))));
+        }
+    }
+
+    let mut tints: Vec<(&(usize, usize), &str)> = Vec::new();
+    if let Some(last_move) = &options.last_move {
+        tints.push((&last_move.origin, LAST_MOVE_COLOR));
+        tints.push((&last_move.destination, LAST_MOVE_COLOR));
+    }
+    tints.extend(
+        options
+            .highlights
+            .iter()
+            .map(|highlight| (&highlight.square, highlight.color.as_str())),
+    );
+    for (square_at, color) in tints {
+        let (x, y) = corner(*square_at);
+        let attributes = format!(
+            "fill=\"{}\" fill-opacity=\"{}\"",
+            escape(color),
+            HIGHLIGHT_OPACITY
+        );
+        svg.push_str(&rect(x, y, square, &attributes));
+    }
+    if let Some(king) = checked_king {
+        let (x, y) = corner(king);
+        svg.push_str(&rect(x, y, square, "fill=\"url(#check)\""));
+    }
+
+    if options.coordinates {
+        let font_size = number(square * 0.3);
+        for index in 0..8 {
+            let (file_x, _) = corner((0, index));
+            let (_, rank_y) = corner((index, 0));
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" \
+                 fill=\"#e5e5e5\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                number(file_x + square / 2.0),
+                number(size - margin / 2.0),
+                font_size,
+                (b'a' + index as u8) as char
+            ));
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" \
+                 fill=\"#e5e5e5\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                number(margin / 2.0),
+                number(rank_y + square / 2.0),
+                font_size,
+                index + 1
+            ));
+        }
+    }
+
+    let scale = square / ARTWORK_SIZE;
+    for (row, pieces) in board.board.iter().enumerate() {
+        for (column, piece) in pieces.iter().enumerate() {
+            let Some(piece) = piece else {
+                continue;
+            };
+            let (x, y) = corner((row, column));
+            svg.push_str(&format!(
+                "<use xlink:href=\"#{}\" transform=\"translate({} {}) scale({})\"/>\n",
+                piece_id(piece),
+                number(x),
+                number(y),
+                number(scale)
+            ));
+        }
+    }
+
+    for arrow in &options.arrows {
+        svg.push_str(&arrow_svg(
+            arrow,
+            corner(arrow.from),
+            corner(arrow.to),
+            square,
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn piece_id(piece: &Piece) -> String {
+    let color = match piece.color {
+        Color::White => "white",
+        Color::Black => "black",
+    };
+    let kind = match piece.kind {
+        PieceKind::King => "king",
+        PieceKind::Queen => "queen",
+        PieceKind::Rook => "rook",
+        PieceKind::Bishop => "bishop",
+        PieceKind::Knight => "knight",
+        PieceKind::Pawn => "pawn",
+    };
+    format!("{}-{}", color, kind)
+}
+
+fn piece_definition(piece: &Piece) -> String {
+    let (fill, contrast) = match piece.color {
+        Color::White => ("#ffffff", "#000000"),
+        Color::Black => ("#000000", "#ffffff"),
+    };
+    let mut group = format!(
+        "<g id=\"{}\" fill=\"{}\" stroke=\"#000000\" stroke-width=\"1.5\" \
+         stroke-linecap=\"round\" stroke-linejoin=\"round\">\n",
+        piece_id(piece),
+        fill
+    );
+    for shape in artwork(piece.kind) {
+        group.push_str(&match shape {
+            Shape::Body(path) => format!("<path d=\"{}\"/>\n", path),
+            Shape::Ball(x, y, r) => format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>\n", x, y, r),
+            Shape::Line(path) => format!("<path d=\"{}\" fill=\"none\"/>\n", path),
+            Shape::Detail(path) => format!(
+                "<path d=\"{}\" fill=\"none\" stroke=\"{}\"/>\n",
+                path, contrast
+            ),
+            Shape::Eye(x, y, r) => format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"none\"/>\n",
+                x, y, r, contrast
+            ),
+        });
+    }
+    group.push_str("</g>\n");
+    group
+}
+
+fn arrow_svg(arrow: &Arrow, from: (f64, f64), to: (f64, f64), square: f64) -> String {
+    let color = escape(&arrow.color);
+    let (x1, y1) = (from.0 + square / 2.0, from.1 + square / 2.0);
+    let (x2, y2) = (to.0 + square / 2.0, to.1 + square / 2.0);
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        return format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" \
+             stroke-width=\"{}\" opacity=\"{}\"/>\n",
+            number(x1),
+            number(y1),
+            number(square * 0.4),
+            color,
+            number(square * 0.08),
+            ARROW_OPACITY
+        );
+    }
+    let (ux, uy) = (dx / length, dy / length);
+    let head_length = square * 0.45;
+    let head_width = square * 0.5;
+    // The tip stops short of the centre so the piece underneath stays visible.
+    let (tip_x, tip_y) = (x2 - ux * square * 0.15, y2 - uy * square * 0.15);
+    let (base_x, base_y) = (tip_x - ux * head_length, tip_y - uy * head_length);
+    let (normal_x, normal_y) = (-uy * head_width / 2.0, ux * head_width / 2.0);
+    format!(
+        "<g opacity=\"{}\"><line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" \
+         stroke-width=\"{}\" stroke-linecap=\"round\"/><polygon points=\"{},{} {},{} {},{}\" \
+         fill=\"{}\"/></g>\n",
+        ARROW_OPACITY,
+        number(x1),
+        number(y1),
+        number(base_x),
+        number(base_y),
+        color,
+        number(square * 0.2),
+        number(tip_x),
+        number(tip_y),
+        number(base_x + normal_x),
+        number(base_y + normal_y),
+        number(base_x - normal_x),
+        number(base_y - normal_y),
+        color
+    )
+}
+
+fn rect(x: f64, y: f64, size: f64, attributes: &str) -> String {
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" {3}/>\n",
+        number(x),
+        number(y),
+        number(size),
+        attributes
+    )
+}
+
+/// A coordinate with at most two decimals, without trailing zeros.
+fn number(value: f64) -> String {
+    format!("{}", (value * 100.0).round() / 100.0)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
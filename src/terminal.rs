@@ -0,0 +1,461 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+use crate::{
+    helpers::{is_checked, square_to_tuple},
+    models::*,
+    pgn::{parse_variant_tag, pgn_date, GameResult, PgnGame},
+    player::{color_name, decided, win_for, EnginePlayer, Player, Termination},
+    render::{BoardStyle, RenderOptions},
+    rng::Rng,
+    search::{pv_to_san, SearchLimits},
+    skill::{Skill, MAX_SKILL_LEVEL},
+    Board, UserMove,
+};
+
+const ENGINE_NAME: &str = "chess";
+const HUMAN_NAME: &str = "Human";
+const DEFAULT_THINKING_TIME: Duration = Duration::from_secs(2);
+const HINT_TIME: Duration = Duration::from_millis(500);
+const HELP: &str = "Enter moves as SAN (Nf3, exd5, O-O, e8=Q), UCI (g1f3, e7e8q) or coordinates
+(g1-f3, e7-e8=Q). Commands:
+  play white|black    play that colour against the engine
+  play human          two players at the keyboard
+  go                  let the engine play the side to move
+  new [VARIANT]       start a new game, optionally of a variant
+  moves [SQUARE]      list the legal moves, or those from one square
+  undo                take back your last move
+  hint                ask the engine for a move
+  resign              give up the game
+  flip                turn the board around
+  style ascii|unicode|color
+                      draw the board in letters, figurines or colour
+  board               draw the board again
+  fen [FEN]           show the position, or set up a new one
+  pgn                 show the game so far
+  level N             engine strength from 0 to 20
+  time SECONDS        engine thinking time per move
+  depth N             engine search depth per move
+  quit                leave";
+
+/// Reads moves and commands from stdin until `quit` or end of input.
+pub fn run() {
+    let mut game = TerminalGame::new();
+    println!("Type a move such as e4 or g1f3, or help for the commands.");
+    game.show();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("{}> ", color_name(game.board.player_turn));
+        let _ = io::stdout().flush();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        if !game.handle_command(&line) {
+            break;
+        }
+    }
+}
+
+/// A game at the terminal between a human and the engine, or two humans.
+pub struct TerminalGame {
+    start: Board,
+    board: Board,
+    moves: Vec<UserMove>,
+    /// The colour the engine plays, or `None` when humans play both sides.
+    engine_color: Option<Color>,
+    limits: SearchLimits,
+    skill: Skill,
+    engine: EnginePlayer,
+    render: RenderOptions,
+    over: Option<(GameResult, Termination)>,
+}
+
+impl TerminalGame {
+    /// A standard game with the human playing white against the engine.
+    pub fn new() -> Self {
+        let limits = SearchLimits::time(DEFAULT_THINKING_TIME);
+        let skill = Skill::full_strength();
+        TerminalGame {
+            start: Board::from_variant(Variant::Standard),
+            board: Board::from_variant(Variant::Standard),
+            moves: Vec::new(),
+            engine_color: Some(Color::Black),
+            engine: EnginePlayer::with_skill(limits.clone(), skill, Rng::from_time().next_u64()),
+            limits,
+            skill,
+            render: RenderOptions::default(),
+            over: None,
+        }
+    }
+
+    /// Handles one line of input, returning `false` once the user quits.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some(command) = words.first() else {
+            return true;
+        };
+        let arguments = &words[1..];
+        let outcome = match *command {
+            "quit" | "exit" => return false,
+            "help" | "?" => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            "play" => self.set_players(arguments),
+            "go" => {
+                self.engine_color = Some(self.board.player_turn);
+                self.engine_reply()
+            }
+            "new" => self.new_game(arguments),
+            "moves" => self.list_moves(arguments),
+            "undo" => self.undo(),
+            "hint" => self.hint(),
+            "resign" => self.resign(),
+            "flip" => {
+                self.render.orientation = self.render.orientation.get_opposite_color();
+                self.show();
+                Ok(())
+            }
+            "style" => self.set_style(arguments),
+            "board" => {
+                self.show();
+                Ok(())
+            }
+            "fen" if arguments.is_empty() => {
+                println!("{}", self.board.to_fen());
+                Ok(())
+            }
+            "fen" => self.set_position(&arguments.join(" ")),
+            "pgn" => self.print_pgn(),
+            "level" => self.set_level(arguments),
+            "time" => self.set_time(arguments),
+            "depth" => self.set_depth(arguments),
+            _ => self.play_user_move(line.trim()),
+        };
+        if let Err(e) = outcome {
+            println!("{}", e);
+        }
+        true
+    }
+
+    /// Draws the board with the last move and whose turn it is, or how the game ended.
+    pub fn show(&self) {
+        self.show_marked(Vec::new());
+    }
+
+    fn show_marked(&self, marked: Vec<(usize, usize)>) {
+        let options = RenderOptions {
+            last_move: self.moves.last().copied(),
+            marked,
+            ..self.render.clone()
+        };
+        println!("\n{}", self.board.render(&options));
+        if let Some(san) = self.last_move_san() {
+            println!("Last move: {}", san);
+        }
+        match self.over {
+            Some((result, termination)) => {
+                println!("Game over: {} ({})", result.to_pgn(), termination)
+            }
+            None if is_checked(&self.board) => {
+                println!("{} to move, in check", color_name(self.board.player_turn))
+            }
+            None => println!("{} to move", color_name(self.board.player_turn)),
+        }
+    }
+
+    fn last_move_san(&self) -> Option<String> {
+        let (last, earlier) = self.moves.split_last()?;
+        let mut board = self.start;
+        for user_move in earlier {
+            board.make_move(user_move);
+        }
+        board.to_san(last).ok()
+    }
+
+    fn set_players(&mut self, arguments: &[&str]) -> Result<(), String> {
+        self.engine_color = match arguments.first().copied() {
+            Some("white") => Some(Color::Black),
+            Some("black") => Some(Color::White),
+            Some("human") => None,
+            _ => return Err("Usage: play white|black|human".to_owned()),
+        };
+        self.engine_reply()
+    }
+
+    fn new_game(&mut self, arguments: &[&str]) -> Result<(), String> {
+        let variant = match arguments.is_empty() {
+            true => self.start.variant,
+            false => parse_variant_tag(Some(&arguments.join(" ")))?,
+        };
+        self.start_from(Board::from_variant(variant));
+        self.show();
+        self.engine_reply()
+    }
+
+    fn set_position(&mut self, fen: &str) -> Result<(), String> {
+        let board = Board::try_from_fen_with_variant(fen, self.start.variant)?;
+        self.start_from(board);
+        self.over = self.game_over();
+        self.show();
+        self.engine_reply()
+    }
+
+    fn start_from(&mut self, board: Board) {
+        self.start = board;
+        self.board = board;
+        self.moves.clear();
+        self.over = None;
+        self.rebuild_engine();
+    }
+
+    /// Lists the legal moves, or those from one square with their destinations marked on the
+    /// board.
+    fn list_moves(&self, arguments: &[&str]) -> Result<(), String> {
+        let origin = match arguments.first() {
+            Some(square) => Some(square_to_tuple(&square.to_lowercase())?),
+            None => None,
+        };
+        let mut sans = Vec::new();
+        let mut destinations = Vec::new();
+        for m in self.board.legal_moves() {
+            if origin.is_none_or(|origin| m.piece_move.origin == origin) {
+                sans.push(self.board.to_san(&m.piece_move)?);
+                destinations.push(m.piece_move.destination);
+            }
+        }
+        match (sans.is_empty(), arguments.first()) {
+            (true, Some(square)) => println!("No legal moves from {}", square),
+            (true, None) => println!("No legal moves"),
+            (false, Some(_)) => {
+                self.show_marked(destinations);
+                println!("{}", sans.join(" "));
+            }
+            (false, None) => println!("{}", sans.join(" ")),
+        }
+        Ok(())
+    }
+
+    /// Takes back one move, or two against the engine so that it is the human's turn again.
+    fn undo(&mut self) -> Result<(), String> {
+        if self.moves.is_empty() {
+            return Err("Nothing to undo".to_owned());
+        }
+        self.moves.pop();
+        if self.engine_color == Some(self.side_to_move_after_replay()) && !self.moves.is_empty() {
+            self.moves.pop();
+        }
+        self.board = self.start;
+        for user_move in &self.moves {
+            self.board.make_move(user_move);
+        }
+        self.over = None;
+        self.show();
+        Ok(())
+    }
+
+    fn side_to_move_after_replay(&self) -> Color {
+        match self.moves.len() % 2 {
+            0 => self.start.player_turn,
+            _ => self.start.player_turn.get_opposite_color(),
+        }
+    }
+
+    fn hint(&self) -> Result<(), String> {
+        self.ensure_ongoing()?;
+        let result = self.board.best_move(SearchLimits::time(HINT_TIME));
+        let san = pv_to_san(&self.board, &result.pv);
+        match san.first() {
+            Some(first) => println!("Hint: {} ({})", first, san.join(" ")),
+            None => println!("No hint available"),
+        }
+        Ok(())
+    }
+
+    fn resign(&mut self) -> Result<(), String> {
+        self.ensure_ongoing()?;
+        let resigning = match self.engine_color {
+            Some(engine) => engine.get_opposite_color(),
+            None => self.board.player_turn,
+        };
+        self.over = Some((
+            win_for(resigning.get_opposite_color()),
+            Termination::Resignation(resigning),
+        ));
+        self.show();
+        Ok(())
+    }
+
+    fn print_pgn(&self) -> Result<(), String> {
+        let name = |color: Color| match self.engine_color == Some(color) {
+            true => ENGINE_NAME,
+            false => HUMAN_NAME,
+        };
+        let result = self.over.map_or(GameResult::Unknown, |(result, _)| result);
+        let tags = vec![
+            ("Event".to_owned(), "Casual game".to_owned()),
+            ("Site".to_owned(), "?".to_owned()),
+            ("Date".to_owned(), pgn_date()),
+            ("Round".to_owned(), "-".to_owned()),
+            ("White".to_owned(), name(Color::White).to_owned()),
+            ("Black".to_owned(), name(Color::Black).to_owned()),
+            ("Result".to_owned(), result.to_pgn().to_owned()),
+        ];
+        let game = PgnGame::from_moves(tags, &self.start, &self.moves, result)?;
+        print!("{}", game.to_pgn()?);
+        Ok(())
+    }
+
+    fn set_style(&mut self, arguments: &[&str]) -> Result<(), String> {
+        let (style, colors) = match arguments.first().copied() {
+            Some("ascii") => (BoardStyle::Ascii, false),
+            Some("unicode") => (BoardStyle::Unicode, false),
+            Some("color") => (BoardStyle::Unicode, true),
+            _ => return Err("Usage: style ascii|unicode|color".to_owned()),
+        };
+        self.render.style = style;
+        self.render.colors = colors;
+        self.show();
+        Ok(())
+    }
+
+    fn set_level(&mut self, arguments: &[&str]) -> Result<(), String> {
+        let level: u32 = parse_argument(arguments, "level N")?;
+        self.skill = Skill::from_level(level);
+        self.rebuild_engine();
+        println!("Engine level {} of {}", self.skill.level, MAX_SKILL_LEVEL);
+        Ok(())
+    }
+
+    fn set_time(&mut self, arguments: &[&str]) -> Result<(), String> {
+        let seconds: f64 = parse_argument(arguments, "time SECONDS")?;
+        let time = match Duration::try_from_secs_f64(seconds) {
+            Ok(time) if !time.is_zero() => time,
+            _ => return Err("Usage: time SECONDS".to_owned()),
+        };
+        self.limits = SearchLimits::time(time);
+        self.rebuild_engine();
+        println!("Engine thinks {} s per move", seconds);
+        Ok(())
+    }
+
+    fn set_depth(&mut self, arguments: &[&str]) -> Result<(), String> {
+        let depth = parse_argument(arguments, "depth N")?;
+        self.limits = SearchLimits::depth(depth);
+        self.rebuild_engine();
+        println!("Engine searches {} plies per move", depth);
+        Ok(())
+    }
+
+    fn rebuild_engine(&mut self) {
+        let seed = Rng::from_time().next_u64();
+        self.engine = EnginePlayer::with_skill(self.limits.clone(), self.skill, seed);
+    }
+
+    fn ensure_ongoing(&self) -> Result<(), String> {
+        match self.over {
+            Some(_) => Err("The game is over; type new to start another".to_owned()),
+            None => Ok(()),
+        }
+    }
+
+    fn play_user_move(&mut self, text: &str) -> Result<(), String> {
+        self.ensure_ongoing()?;
+        if self.engine_color == Some(self.board.player_turn) {
+            return Err("It is the engine's turn; type go to let it move".to_owned());
+        }
+        let user_move = self.parse_move(text)?;
+        self.play(user_move);
+        self.show();
+        self.engine_reply()
+    }
+
+    /// Lets the engine move if it is its turn and the game goes on.
+    fn engine_reply(&mut self) -> Result<(), String> {
+        if self.over.is_some() || self.engine_color != Some(self.board.player_turn) {
+            return Ok(());
+        }
+        println!("Thinking...");
+        let user_move = self.engine.choose_move(&self.board, &self.history());
+        println!("{} plays {}", ENGINE_NAME, self.board.to_san(&user_move)?);
+        self.play(user_move);
+        self.show();
+        Ok(())
+    }
+
+    fn play(&mut self, user_move: UserMove) {
+        self.board.make_move(&user_move);
+        self.moves.push(user_move);
+        self.over = self.game_over();
+    }
+
+    /// The positions before each move of the game, oldest first.
+    fn history(&self) -> Vec<Board> {
+        let mut history = Vec::new();
+        let mut board = self.start;
+        for user_move in &self.moves {
+            history.push(board);
+            board.make_move(user_move);
+        }
+        history
+    }
+
+    /// Checks the rules, counting repetitions over the whole game.
+    fn game_over(&self) -> Option<(GameResult, Termination)> {
+        let mut seen = HashMap::new();
+        let mut board = self.start;
+        for user_move in &self.moves {
+            *seen.entry(board.hash()).or_insert(0) += 1;
+            board.make_move(user_move);
+        }
+        decided(&board, &mut seen)
+    }
+
+    /// Reads SAN first, then UCI or coordinate notation with optional `-`, `x` and `=`. A
+    /// promotion without a piece is taken as a queen.
+    fn parse_move(&self, text: &str) -> Result<UserMove, String> {
+        if let Ok(user_move) = self.board.parse_san(text) {
+            return Ok(user_move);
+        }
+        let unrecognised = || format!("Unrecognised or illegal move: {}", text);
+        let coordinates: String = text
+            .chars()
+            .filter(|c| !matches!(c, '-' | 'x' | ':' | '=' | ' '))
+            .collect();
+        let wanted = UserMove::from_uci(&coordinates.to_lowercase()).map_err(|_| unrecognised())?;
+        let legal: Vec<UserMove> = self
+            .board
+            .legal_moves()
+            .into_iter()
+            .map(|m| m.piece_move)
+            .collect();
+        if legal.contains(&wanted) {
+            return Ok(wanted);
+        }
+        let queening = UserMove {
+            promotion_request: Some(PieceKind::Queen),
+            ..wanted
+        };
+        match wanted.promotion_request.is_none() && legal.contains(&queening) {
+            true => Ok(queening),
+            false => Err(unrecognised()),
+        }
+    }
+}
+
+impl Default for TerminalGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_argument<T: std::str::FromStr>(arguments: &[&str], usage: &str) -> Result<T, String> {
+    arguments
+        .first()
+        .and_then(|argument| argument.parse().ok())
+        .ok_or(format!("Usage: {}", usage))
+}
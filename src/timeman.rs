@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use crate::UserMove;
+
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+const MAX_MOVES_TO_GO: u32 = 50;
+const SCORE_DROP_MARGIN: i32 = 30;
+const MIN_THINKING_TIME: Duration = Duration::from_millis(1);
+
+/// The side to move's clock, as given by `go wtime/btime winc/binc movestogo` in UCI.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Clock {
+    pub remaining: Duration,
+    pub increment: Duration,
+    pub moves_to_go: Option<u32>,
+    /// Time lost per move to communication and GUI lag, kept in reserve.
+    pub move_overhead: Duration,
+}
+
+impl Clock {
+    pub fn new(remaining: Duration, increment: Duration) -> Self {
+        Clock {
+            remaining,
+            increment,
+            ..Default::default()
+        }
+    }
+}
+
+/// Splits the clock into a per-move budget.
+///
+/// The soft limit is the time the search aims to use; it is checked between iterations and is
+/// scaled down while the best move stays the same and up when the score drops. The hard limit is
+/// never exceeded and aborts the search mid-iteration.
+#[derive(Clone, Debug)]
+pub struct TimeManager {
+    soft_limit: Duration,
+    hard_limit: Duration,
+    best_move_stability: u32,
+    previous_best: Option<UserMove>,
+    previous_score: Option<i32>,
+    score_dropped: bool,
+}
+
+impl TimeManager {
+    pub fn new(clock: &Clock) -> Self {
+        let available = clock
+            .remaining
+            .saturating_sub(clock.move_overhead)
+            .max(MIN_THINKING_TIME);
+        let moves_to_go = clock
+            .moves_to_go
+            .unwrap_or(DEFAULT_MOVES_TO_GO)
+            .clamp(1, MAX_MOVES_TO_GO);
+
+        let base = available / moves_to_go + clock.increment * 3 / 4;
+        let soft_limit = base.min(available / 2).max(MIN_THINKING_TIME);
+        let hard_cap = if moves_to_go == 1 {
+            available * 9 / 10
+        } else {
+            available * 4 / 10
+        };
+        let hard_limit = (soft_limit * 4).min(hard_cap).max(soft_limit);
+        TimeManager {
+            soft_limit,
+            hard_limit,
+            best_move_stability: 0,
+            previous_best: None,
+            previous_score: None,
+            score_dropped: false,
+        }
+    }
+
+    pub fn soft_limit(&self) -> Duration {
+        self.soft_limit
+    }
+
+    pub fn hard_limit(&self) -> Duration {
+        self.hard_limit
+    }
+
+    /// Feeds the result of a completed iteration into the stability and score-drop tracking.
+    pub fn update(&mut self, best_move: Option<UserMove>, score: i32) {
+        if best_move.is_some() && best_move == self.previous_best {
+            self.best_move_stability += 1;
+        } else {
+            self.best_move_stability = 0;
+        }
+        self.score_dropped = self
+            .previous_score
+            .is_some_and(|previous| score < previous - SCORE_DROP_MARGIN);
+        self.previous_best = best_move;
+        self.previous_score = Some(score);
+    }
+
+    /// The soft limit after adjusting for best-move stability and score drops.
+    pub fn adjusted_soft_limit(&self) -> Duration {
+        let stability_factor = match self.best_move_stability {
+            0 => 1.6,
+            1 => 1.2,
+            2 => 1.0,
+            3 => 0.85,
+            _ => 0.7,
+        };
+        let drop_factor = if self.score_dropped { 1.5 } else { 1.0 };
+        self.soft_limit
+            .mul_f64(stability_factor * drop_factor)
+            .min(self.hard_limit)
+    }
+
+    /// Whether a new iteration should be started after `elapsed` time, given that the next
+    /// iteration usually takes longer than all previous ones together.
+    pub fn should_stop_iterating(&self, elapsed: Duration) -> bool {
+        elapsed >= self.adjusted_soft_limit() / 2
+    }
+}
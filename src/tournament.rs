@@ -0,0 +1,1054 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    endgame::{Tablebase, Wdl},
+    models::*,
+    pgn::{parse_games, pgn_date, GameResult, PgnGame},
+    player::{decided, win_for, PlayedGame, Termination},
+    search::MATE_SCORE,
+    Board, UserMove,
+};
+
+/// How long an engine gets to answer `uci` and `isready`.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long an engine gets to exit after `quit` before it is killed.
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Two-sided 95% quantile of the normal distribution.
+const CONFIDENCE_95: f64 = 1.959964;
+/// Games of each outcome added to the score before computing an SPRT.
+const SPRT_PRIOR: f64 = 0.5;
+
+/// How to start a UCI engine and the options to give it.
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    pub name: String,
+    pub command: String,
+    pub arguments: Vec<String>,
+    /// Sent with `setoption` after the handshake, in order.
+    pub options: Vec<(String, String)>,
+}
+
+impl EngineConfig {
+    /// An engine named after its executable, with no arguments or options.
+    pub fn new(command: &str) -> Self {
+        let name = Path::new(command)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(command);
+        EngineConfig {
+            name: name.to_owned(),
+            command: command.to_owned(),
+            arguments: Vec::new(),
+            options: Vec::new(),
+        }
+    }
+}
+
+/// An engine's answer to `go`.
+#[derive(Clone, Debug)]
+pub struct EngineReply {
+    /// The move as sent, which may not be legal.
+    pub best_move: String,
+    /// The last score reported for the first line, from the engine's side, with mates scored
+    /// like `search::MATE_SCORE`.
+    pub score: Option<i32>,
+    pub depth: u32,
+    pub elapsed: Duration,
+}
+
+impl EngineReply {
+    /// The PGN comment for the move, in the usual `+0.35/12 0.215s` form.
+    fn comment(&self) -> String {
+        let seconds = self.elapsed.as_secs_f64();
+        match self.score {
+            Some(score) if score.abs() >= MATE_SCORE - 1000 => {
+                let moves = (MATE_SCORE - score.abs() + 1) / 2;
+                let sign = if score > 0 { "+" } else { "-" };
+                format!("{}M{}/{} {:.3}s", sign, moves, self.depth, seconds)
+            }
+            Some(score) => format!(
+                "{:+.2}/{} {:.3}s",
+                score as f64 / 100.0,
+                self.depth,
+                seconds
+            ),
+            None => format!("{:.3}s", seconds),
+        }
+    }
+}
+
+/// A UCI engine running as a child process. Its output is read on a separate thread so that
+/// waiting for a reply can time out.
+pub struct UciProcess {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: mpsc::Receiver<String>,
+    variant: Variant,
+}
+
+impl UciProcess {
+    /// Starts the engine, completes the handshake and sets its options.
+    pub fn spawn(config: &EngineConfig) -> Result<Self, String> {
+        let mut child = Command::new(&config.command)
+            .args(&config.arguments)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Cannot start {}: {}", config.command, e))?;
+        let stdin = child.stdin.take().ok_or("Engine stdin is not piped")?;
+        let stdout = child.stdout.take().ok_or("Engine stdout is not piped")?;
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut process = UciProcess {
+            name: config.name.clone(),
+            child,
+            stdin,
+            lines,
+            variant: Variant::Standard,
+        };
+        process.send("uci")?;
+        process.wait_for("uciok", Some(HANDSHAKE_TIMEOUT))?;
+        for (name, value) in &config.options {
+            process.send(&format!("setoption name {} value {}", name, value))?;
+        }
+        process.sync()?;
+        Ok(process)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Waits until the engine has dealt with everything sent so far, discarding its output.
+    pub fn sync(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+        self.wait_for("readyok", Some(HANDSHAKE_TIMEOUT))
+            .map(|_| ())
+    }
+
+    /// Prepares for a new game, switching `UCI_Variant` when the variant changes.
+    pub fn new_game(&mut self, variant: Variant) -> Result<(), String> {
+        self.send("ucinewgame")?;
+        if variant != self.variant {
+            self.send(&format!(
+                "setoption name UCI_Variant value {}",
+                uci_variant(variant)
+            ))?;
+            self.variant = variant;
+        }
+        self.sync()
+    }
+
+    /// Asks for a move in the position reached from `start` by `moves`, sending `limits` after
+    /// `go`. Gives `None` if no move arrives within `timeout`, after telling the engine to stop.
+    pub fn go(
+        &mut self,
+        start: &Board,
+        moves: &[UserMove],
+        limits: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Option<EngineReply>, String> {
+        let mut position = format!("position fen {}", start.to_fen());
+        if !moves.is_empty() {
+            position.push_str(" moves");
+            for m in moves {
+                position.push(' ');
+                position.push_str(&m.to_uci());
+            }
+        }
+        self.send(&position)?;
+        self.send(&format!("go {}", limits))?;
+
+        let started = Instant::now();
+        let deadline = timeout.and_then(|timeout| started.checked_add(timeout));
+        let mut score = None;
+        let mut depth = 0;
+        loop {
+            let Some(line) = self.next_line(deadline)? else {
+                self.send("stop")?;
+                return Ok(None);
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.first() {
+                Some(&"info") => parse_info(&words, &mut score, &mut depth),
+                Some(&"bestmove") => {
+                    return Ok(Some(EngineReply {
+                        best_move: words.get(1).unwrap_or(&"(none)").to_string(),
+                        score,
+                        depth,
+                        elapsed: started.elapsed(),
+                    }))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("{}: {}", self.name, e))
+    }
+
+    /// Reads lines until one starts with `token`.
+    fn wait_for(&mut self, token: &str, timeout: Option<Duration>) -> Result<String, String> {
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        loop {
+            let line = self
+                .next_line(deadline)?
+                .ok_or(format!("{} did not send {}", self.name, token))?;
+            if line.split_whitespace().next() == Some(token) {
+                return Ok(line);
+            }
+        }
+    }
+
+    /// The next line of output, or `None` once `deadline` has passed. Fails when the engine has
+    /// exited.
+    fn next_line(&self, deadline: Option<Instant>) -> Result<Option<String>, String> {
+        let received = match deadline {
+            Some(deadline) => self
+                .lines
+                .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => self
+                .lines
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(line) => Ok(Some(line)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(format!("{} has exited", self.name)),
+        }
+    }
+}
+
+impl Drop for UciProcess {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(Some(_)) | Err(_) => return,
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Picks the score and depth out of an `info` line, ignoring lines after the first of MultiPV.
+fn parse_info(words: &[&str], score: &mut Option<i32>, depth: &mut u32) {
+    let value = |key: &str| {
+        words
+            .iter()
+            .position(|word| *word == key)
+            .and_then(|index| words.get(index + 1))
+    };
+    if words.get(1) == Some(&"string") || value("multipv").is_some_and(|line| *line != "1") {
+        return;
+    }
+    if let Some(reported) = value("depth").and_then(|depth| depth.parse().ok()) {
+        *depth = reported;
+    }
+    let Some(index) = words.iter().position(|word| *word == "score") else {
+        return;
+    };
+    let amount: Option<i32> = words.get(index + 2).and_then(|amount| amount.parse().ok());
+    match (words.get(index + 1), amount) {
+        (Some(&"cp"), Some(centipawns)) => *score = Some(centipawns),
+        (Some(&"mate"), Some(moves)) if moves > 0 => *score = Some(MATE_SCORE - (2 * moves - 1)),
+        (Some(&"mate"), Some(moves)) => *score = Some(-(MATE_SCORE - 2 * -moves)),
+        _ => {}
+    }
+}
+
+fn uci_variant(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Standard => "chess",
+        Variant::Atomic => "atomic",
+        Variant::Antichess => "antichess",
+        Variant::Horde => "horde",
+        Variant::RacingKings => "racingkings",
+    }
+}
+
+/// A starting position for a game, given as a position and the moves already played from it.
+#[derive(Clone)]
+pub struct Opening {
+    pub start: Board,
+    pub moves: Vec<UserMove>,
+}
+
+impl Opening {
+    pub fn from_variant(variant: Variant) -> Self {
+        Opening {
+            start: Board::from_variant(variant),
+            moves: Vec::new(),
+        }
+    }
+
+    /// The first `max_plies` moves of every game in a PGN collection, skipping games that do not
+    /// parse.
+    pub fn from_pgn(text: &str, max_plies: usize) -> Vec<Self> {
+        parse_games(text)
+            .into_iter()
+            .filter_map(|game| {
+                let game = game.ok()?;
+                Some(Opening {
+                    start: game.start_board().ok()?,
+                    moves: game
+                        .moves
+                        .iter()
+                        .take(max_plies)
+                        .map(|m| m.user_move)
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// One position per line of an EPD or FEN file. EPD operations after the first four fields
+    /// are ignored.
+    pub fn from_epd(text: &str, variant: Variant) -> Result<Vec<Self>, String> {
+        let mut openings = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 4 {
+                return Err(format!("Invalid EPD: {}", line));
+            }
+            let counters = match (fields.get(4), fields.get(5)) {
+                (Some(half_moves), Some(moves))
+                    if half_moves.parse::<usize>().is_ok() && moves.parse::<usize>().is_ok() =>
+                {
+                    format!("{} {}", half_moves, moves)
+                }
+                _ => "0 1".to_owned(),
+            };
+            let fen = format!("{} {}", fields[..4].join(" "), counters);
+            openings.push(Opening {
+                start: Board::try_from_fen_with_variant(&fen, variant)?,
+                moves: Vec::new(),
+            });
+        }
+        Ok(openings)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum TimeControl {
+    /// Time for the whole game plus an increment after every move.
+    Clock {
+        base: Duration,
+        increment: Duration,
+    },
+    MoveTime(Duration),
+    Depth(u32),
+    Nodes(u64),
+}
+
+impl TimeControl {
+    /// Parses a clock in seconds as `base+increment`, e.g. `10+0.1`, or just `base`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid time control: {}", text);
+        let seconds = |text: &str| {
+            text.parse::<f64>()
+                .ok()
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .ok_or_else(invalid)
+        };
+        let (base, increment) = text.split_once('+').unwrap_or((text, "0"));
+        Ok(TimeControl::Clock {
+            base: seconds(base)?,
+            increment: seconds(increment)?,
+        })
+    }
+
+    /// The value of the PGN `TimeControl` tag, if the game was played on a clock.
+    fn pgn_tag(&self) -> Option<String> {
+        match self {
+            TimeControl::Clock { base, increment } => Some(match increment.is_zero() {
+                true => format!("{}", base.as_secs_f64()),
+                false => format!("{}+{}", base.as_secs_f64(), increment.as_secs_f64()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Rules for ending games early. Everything is off by default.
+#[derive(Clone)]
+pub struct Adjudication {
+    /// A side resigns once its engine has reported at least this much against it for
+    /// `resign_moves` moves in a row, with the opponent's engine agreeing over its own moves.
+    pub resign_score: Option<i32>,
+    pub resign_moves: u32,
+    /// The game is drawn once both engines have reported scores within this much of zero for
+    /// `draw_moves` moves each, from move `draw_move_number` on.
+    pub draw_score: Option<i32>,
+    pub draw_moves: u32,
+    pub draw_move_number: usize,
+    /// Games still going after this many moves each are drawn.
+    pub max_moves: Option<usize>,
+    /// Positions covered by the tables end the game with their exact result.
+    pub tablebase: Option<Arc<Tablebase>>,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Adjudication {
+            resign_score: None,
+            resign_moves: 3,
+            draw_score: None,
+            draw_moves: 8,
+            draw_move_number: 40,
+            max_moves: None,
+            tablebase: None,
+        }
+    }
+}
+
+/// Consecutive scores seen so far in one game.
+struct Adjudicator<'a> {
+    rules: &'a Adjudication,
+    /// Per colour, moves in a row with the engine reporting itself lost or won.
+    losing: [u32; 2],
+    winning: [u32; 2],
+    level: u32,
+}
+
+impl<'a> Adjudicator<'a> {
+    fn new(rules: &'a Adjudication) -> Self {
+        Adjudicator {
+            rules,
+            losing: [0; 2],
+            winning: [0; 2],
+            level: 0,
+        }
+    }
+
+    /// Checks a position before the side to move is asked for a move.
+    fn before_move(&self, board: &Board, plies: usize) -> Option<(GameResult, Termination)> {
+        if let Some(probe) = self.rules.tablebase.as_ref().and_then(|tb| tb.probe(board)) {
+            let result = match probe.wdl {
+                Wdl::Win => win_for(board.player_turn),
+                Wdl::Loss => win_for(board.player_turn.get_opposite_color()),
+                Wdl::Draw => GameResult::Draw,
+            };
+            return Some((result, Termination::Tablebase));
+        }
+        match self.rules.max_moves {
+            Some(max_moves) if plies >= 2 * max_moves => {
+                Some((GameResult::Draw, Termination::MoveLimit))
+            }
+            _ => None,
+        }
+    }
+
+    /// Records the score `mover`'s engine gave for the move it just played in `board`.
+    fn after_move(
+        &mut self,
+        board: &Board,
+        mover: Color,
+        score: Option<i32>,
+    ) -> Option<(GameResult, Termination)> {
+        let side = side_index(mover);
+        let other = 1 - side;
+        if let Some(threshold) = self.rules.resign_score {
+            let score = score.unwrap_or(0);
+            self.losing[side] = if score <= -threshold {
+                self.losing[side] + 1
+            } else {
+                0
+            };
+            self.winning[side] = if score >= threshold {
+                self.winning[side] + 1
+            } else {
+                0
+            };
+            let moves = self.rules.resign_moves.max(1);
+            if self.losing[side] >= moves && self.winning[other] >= moves {
+                return Some((
+                    win_for(mover.get_opposite_color()),
+                    Termination::Resignation(mover),
+                ));
+            }
+            if self.winning[side] >= moves && self.losing[other] >= moves {
+                return Some((
+                    win_for(mover),
+                    Termination::Resignation(mover.get_opposite_color()),
+                ));
+            }
+        }
+        if let Some(threshold) = self.rules.draw_score {
+            let level = score.is_some_and(|score| score.abs() <= threshold);
+            self.level = match level && board.num_moves >= self.rules.draw_move_number {
+                true => self.level + 1,
+                false => 0,
+            };
+            if self.level >= 2 * self.rules.draw_moves.max(1) {
+                return Some((GameResult::Draw, Termination::AdjudicatedDraw));
+            }
+        }
+        None
+    }
+}
+
+fn side_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+/// Wins, losses and draws from one side's point of view.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Score {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+/// An Elo difference with the half-width of its 95% confidence interval.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EloEstimate {
+    pub difference: f64,
+    pub margin: f64,
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    /// Points as a fraction of the games played.
+    pub fn ratio(&self) -> f64 {
+        match self.games() {
+            0 => 0.5,
+            games => self.points() / games as f64,
+        }
+    }
+
+    /// Adds `result` for the player who had `color`. Unfinished games are not counted.
+    pub fn add(&mut self, result: GameResult, color: Color) {
+        match (result, color) {
+            (GameResult::WhiteWins, Color::White) | (GameResult::BlackWins, Color::Black) => {
+                self.wins += 1
+            }
+            (GameResult::WhiteWins, Color::Black) | (GameResult::BlackWins, Color::White) => {
+                self.losses += 1
+            }
+            (GameResult::Draw, _) => self.draws += 1,
+            (GameResult::Unknown, _) => {}
+        }
+    }
+
+    /// The Elo difference the score suggests. There is none to give before the first game or
+    /// while one side has scored every point.
+    pub fn elo(&self) -> Option<EloEstimate> {
+        let ratio = self.ratio();
+        if self.games() == 0 || ratio <= 0.0 || ratio >= 1.0 {
+            return None;
+        }
+        let (_, variance) = moments(self.wins as f64, self.draws as f64, self.losses as f64);
+        let deviation = (variance / self.games() as f64).sqrt();
+        let bound = |ratio: f64| elo_difference(ratio.clamp(1e-6, 1.0 - 1e-6));
+        Some(EloEstimate {
+            difference: elo_difference(ratio),
+            margin: (bound(ratio + CONFIDENCE_95 * deviation)
+                - bound(ratio - CONFIDENCE_95 * deviation))
+                / 2.0,
+        })
+    }
+}
+
+/// Mean and per-game variance of the points scored, from counts that need not be whole.
+fn moments(wins: f64, draws: f64, losses: f64) -> (f64, f64) {
+    let games = wins + draws + losses;
+    let mean = (wins + draws / 2.0) / games;
+    let variance =
+        (wins * (1.0 - mean).powi(2) + draws * (0.5 - mean).powi(2) + losses * mean.powi(2))
+            / games;
+    (mean, variance)
+}
+
+fn elo_difference(ratio: f64) -> f64 {
+    -400.0 * (1.0 / ratio - 1.0).log10()
+}
+
+fn expected_ratio(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// A sequential probability ratio test of the hypothesis that the difference is `elo1` against
+/// the hypothesis that it is `elo0`, with false positive rate `alpha` and false negative rate
+/// `beta`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SprtDecision {
+    /// The difference is `elo0` or worse.
+    AcceptH0,
+    /// The difference is `elo1` or better.
+    AcceptH1,
+}
+
+impl Sprt {
+    /// Log-likelihood ratio of the two hypotheses, using the normal approximation of the
+    /// win/draw/loss distribution.
+    pub fn llr(&self, score: &Score) -> f64 {
+        if score.games() == 0 {
+            return 0.0;
+        }
+        // Half a game of each outcome keeps the variance above zero while one side wins or
+        // draws everything.
+        let (ratio, variance) = moments(
+            score.wins as f64 + SPRT_PRIOR,
+            score.draws as f64 + SPRT_PRIOR,
+            score.losses as f64 + SPRT_PRIOR,
+        );
+        let (ratio0, ratio1) = (expected_ratio(self.elo0), expected_ratio(self.elo1));
+        (ratio1 - ratio0) * (2.0 * ratio - ratio0 - ratio1) * score.games() as f64
+            / (2.0 * variance)
+    }
+
+    /// The log-likelihood ratios at which H0 and H1 are accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn decision(&self, score: &Score) -> Option<SprtDecision> {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        match llr {
+            llr if llr <= lower => Some(SprtDecision::AcceptH0),
+            llr if llr >= upper => Some(SprtDecision::AcceptH1),
+            _ => None,
+        }
+    }
+}
+
+/// A finished game in a tournament.
+#[derive(Clone)]
+pub struct GameRecord {
+    /// Position in the schedule, counting from 1.
+    pub number: usize,
+    /// Indices into `Tournament::engines`.
+    pub white: usize,
+    pub black: usize,
+    pub game: PlayedGame,
+    /// For every move, `book` or the engine's score, depth and time.
+    pub comments: Vec<String>,
+}
+
+/// Two engines' games against each other, scored for `first`.
+#[derive(Clone, Debug)]
+pub struct Pairing {
+    pub first: usize,
+    pub second: usize,
+    pub score: Score,
+}
+
+#[derive(Clone, Debug)]
+pub struct TournamentReport {
+    pub names: Vec<String>,
+    /// Each engine's score against the rest of the field.
+    pub standings: Vec<Score>,
+    pub pairings: Vec<Pairing>,
+    /// Set once the SPRT of a two-engine match has been decided, which ends the match.
+    pub sprt: Option<SprtDecision>,
+}
+
+impl TournamentReport {
+    fn record(&mut self, record: &GameRecord) {
+        let result = record.game.result;
+        self.standings[record.white].add(result, Color::White);
+        self.standings[record.black].add(result, Color::Black);
+        for pairing in &mut self.pairings {
+            if pairing.first == record.white && pairing.second == record.black {
+                pairing.score.add(result, Color::White);
+            } else if pairing.first == record.black && pairing.second == record.white {
+                pairing.score.add(result, Color::Black);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Scheduled {
+    white: usize,
+    black: usize,
+    opening: usize,
+}
+
+/// A round robin between UCI engines. Each round, every pair of engines plays the round's
+/// opening twice, once with each colour.
+pub struct Tournament {
+    pub engines: Vec<EngineConfig>,
+    pub time_control: TimeControl,
+    /// Time an engine may overrun its clock or move time before it forfeits, for process and
+    /// pipe lag.
+    pub time_margin: Duration,
+    /// Used in order and reused from the start when there are more rounds. Without openings,
+    /// games start from the standard position.
+    pub openings: Vec<Opening>,
+    pub rounds: usize,
+    /// Games played at once, each with its own engine processes.
+    pub concurrency: usize,
+    pub adjudication: Adjudication,
+    /// Ends a two-engine match early once the test is decided.
+    pub sprt: Option<Sprt>,
+    /// Finished games are appended here as PGN.
+    pub pgn_path: Option<PathBuf>,
+    pub event: String,
+}
+
+impl Tournament {
+    pub fn new(engines: Vec<EngineConfig>) -> Self {
+        Tournament {
+            engines,
+            time_control: TimeControl::Clock {
+                base: Duration::from_secs(10),
+                increment: Duration::from_millis(100),
+            },
+            time_margin: Duration::from_millis(50),
+            openings: Vec::new(),
+            rounds: 1,
+            concurrency: 1,
+            adjudication: Adjudication::default(),
+            sprt: None,
+            pgn_path: None,
+            event: "Engine match".to_owned(),
+        }
+    }
+
+    /// Plays every game, calling `on_game` after each one with the scores so far. Fails if an
+    /// engine cannot be started or the PGN file cannot be written.
+    pub fn run(
+        &self,
+        mut on_game: impl FnMut(&GameRecord, &TournamentReport),
+    ) -> Result<TournamentReport, String> {
+        if self.engines.len() < 2 {
+            return Err("A tournament needs at least two engines".to_owned());
+        }
+        let mut report = TournamentReport {
+            names: self
+                .engines
+                .iter()
+                .map(|engine| engine.name.clone())
+                .collect(),
+            standings: vec![Score::default(); self.engines.len()],
+            pairings: Vec::new(),
+            sprt: None,
+        };
+        for first in 0..self.engines.len() {
+            for second in first + 1..self.engines.len() {
+                report.pairings.push(Pairing {
+                    first,
+                    second,
+                    score: Score::default(),
+                });
+            }
+        }
+        let mut pgn_file = match &self.pgn_path {
+            Some(path) => Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?,
+            ),
+            None => None,
+        };
+
+        let openings = match self.openings.is_empty() {
+            true => vec![Opening::from_variant(Variant::Standard)],
+            false => self.openings.clone(),
+        };
+        let schedule = self.schedule(&report.pairings, openings.len());
+        let next = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| -> Result<(), String> {
+            let (schedule, openings, next, stop) = (&schedule, &openings, &next, &stop);
+            for _ in 0..self.concurrency.max(1) {
+                let sender = sender.clone();
+                scope.spawn(move || self.work(schedule, openings, next, stop, sender));
+            }
+            drop(sender);
+
+            // Returning early drops the receiver, which stops the workers after their games.
+            for outcome in receiver {
+                let record = outcome?;
+                report.record(&record);
+                if let Some(file) = &mut pgn_file {
+                    let text = self.pgn_game(&record)?.to_pgn()?;
+                    writeln!(file, "{}", text).map_err(|e| e.to_string())?;
+                }
+                if let (Some(sprt), [pairing], None) =
+                    (self.sprt, report.pairings.as_slice(), report.sprt)
+                {
+                    report.sprt = sprt.decision(&pairing.score);
+                }
+                on_game(&record, &report);
+                if report.sprt.is_some() {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+            Ok(())
+        })?;
+        Ok(report)
+    }
+
+    /// The game as PGN, with the engines' evaluations as move comments.
+    pub fn pgn_game(&self, record: &GameRecord) -> Result<PgnGame, String> {
+        let game = &record.game;
+        let mut tags = vec![
+            ("Event".to_owned(), self.event.clone()),
+            ("Site".to_owned(), "?".to_owned()),
+            ("Date".to_owned(), pgn_date()),
+            ("Round".to_owned(), record.number.to_string()),
+            ("White".to_owned(), self.engines[record.white].name.clone()),
+            ("Black".to_owned(), self.engines[record.black].name.clone()),
+            ("Result".to_owned(), game.result.to_pgn().to_owned()),
+        ];
+        if let Some(time_control) = self.time_control.pgn_tag() {
+            tags.push(("TimeControl".to_owned(), time_control));
+        }
+        tags.push((
+            "Termination".to_owned(),
+            game.termination.pgn_tag().to_owned(),
+        ));
+        tags.push(("PlyCount".to_owned(), game.moves.len().to_string()));
+
+        let mut pgn = PgnGame::from_moves(tags, &game.start, &game.moves, game.result)?;
+        for (pgn_move, comment) in pgn.moves.iter_mut().zip(&record.comments) {
+            pgn_move.comment = Some(comment.clone());
+        }
+        if let Some(last) = pgn.moves.last_mut() {
+            let comment = last.comment.take().unwrap_or_default();
+            last.comment = Some(format!("{}, {}", comment, game.termination));
+        }
+        Ok(pgn)
+    }
+
+    fn schedule(&self, pairings: &[Pairing], openings: usize) -> Vec<Scheduled> {
+        let mut schedule = Vec::new();
+        for round in 0..self.rounds {
+            for pairing in pairings {
+                for (white, black) in [
+                    (pairing.first, pairing.second),
+                    (pairing.second, pairing.first),
+                ] {
+                    schedule.push(Scheduled {
+                        white,
+                        black,
+                        opening: round % openings,
+                    });
+                }
+            }
+        }
+        schedule
+    }
+
+    /// Plays games from the schedule until it runs out or the tournament stops, keeping engine
+    /// processes alive between games.
+    fn work(
+        &self,
+        schedule: &[Scheduled],
+        openings: &[Opening],
+        next: &AtomicUsize,
+        stop: &AtomicBool,
+        results: mpsc::Sender<Result<GameRecord, String>>,
+    ) {
+        let mut processes: HashMap<usize, UciProcess> = HashMap::new();
+        while !stop.load(Ordering::Relaxed) {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(scheduled) = schedule.get(index) else {
+                break;
+            };
+            let outcome = self.play_scheduled(index + 1, scheduled, openings, &mut processes);
+            if results.send(outcome).is_err() {
+                break;
+            }
+        }
+    }
+
+    fn play_scheduled(
+        &self,
+        number: usize,
+        scheduled: &Scheduled,
+        openings: &[Opening],
+        processes: &mut HashMap<usize, UciProcess>,
+    ) -> Result<GameRecord, String> {
+        for engine in [scheduled.white, scheduled.black] {
+            if let Entry::Vacant(entry) = processes.entry(engine) {
+                entry.insert(UciProcess::spawn(&self.engines[engine])?);
+            }
+        }
+        let mut white = processes.remove(&scheduled.white).unwrap();
+        let mut black = processes.remove(&scheduled.black).unwrap();
+        let (game, comments) = self.play(&mut white, &mut black, &openings[scheduled.opening]);
+        // An engine that went away is started again for its next game.
+        if game.termination != Termination::Disconnected(Color::White) {
+            processes.insert(scheduled.white, white);
+        }
+        if game.termination != Termination::Disconnected(Color::Black) {
+            processes.insert(scheduled.black, black);
+        }
+        Ok(GameRecord {
+            number,
+            white: scheduled.white,
+            black: scheduled.black,
+            game,
+            comments,
+        })
+    }
+
+    fn play(
+        &self,
+        white: &mut UciProcess,
+        black: &mut UciProcess,
+        opening: &Opening,
+    ) -> (PlayedGame, Vec<String>) {
+        let mut board = opening.start;
+        let mut moves = Vec::new();
+        let mut comments = Vec::new();
+        let mut seen: HashMap<u64, u32> = HashMap::new();
+        for user_move in &opening.moves {
+            *seen.entry(board.hash()).or_insert(0) += 1;
+            board.make_move(user_move);
+            moves.push(*user_move);
+            comments.push("book".to_owned());
+        }
+
+        let mut clocks = match self.time_control {
+            TimeControl::Clock { base, .. } => [base; 2],
+            _ => [Duration::ZERO; 2],
+        };
+        let mut adjudicator = Adjudicator::new(&self.adjudication);
+        let variant = opening.start.variant;
+        let ready = match (white.new_game(variant), black.new_game(variant)) {
+            (Err(_), _) => Some((
+                GameResult::BlackWins,
+                Termination::Disconnected(Color::White),
+            )),
+            (_, Err(_)) => Some((
+                GameResult::WhiteWins,
+                Termination::Disconnected(Color::Black),
+            )),
+            _ => None,
+        };
+
+        let (result, termination) = loop {
+            if let Some(decided) = ready.or_else(|| decided(&board, &mut seen)) {
+                break decided;
+            }
+            if let Some(adjudicated) = adjudicator.before_move(&board, moves.len()) {
+                break adjudicated;
+            }
+            let mover = board.player_turn;
+            let forfeit = |termination| (win_for(mover.get_opposite_color()), termination);
+            let process = match mover {
+                Color::White => &mut *white,
+                Color::Black => &mut *black,
+            };
+            let (limits, allowed) = self.go_limits(&clocks, mover);
+            let timeout = allowed.map(|allowed| allowed + self.time_margin);
+            let reply = match process.go(&opening.start, &moves, &limits, timeout) {
+                Ok(Some(reply)) => reply,
+                Ok(None) => break forfeit(Termination::TimeForfeit(mover)),
+                Err(_) => break forfeit(Termination::Disconnected(mover)),
+            };
+            if allowed.is_some_and(|allowed| reply.elapsed > allowed + self.time_margin) {
+                break forfeit(Termination::TimeForfeit(mover));
+            }
+            if let TimeControl::Clock { increment, .. } = self.time_control {
+                let clock = &mut clocks[side_index(mover)];
+                *clock = clock.saturating_sub(reply.elapsed) + increment;
+            }
+
+            let played = UserMove::from_uci(&reply.best_move)
+                .ok()
+                .and_then(|played| {
+                    board
+                        .legal_moves()
+                        .into_iter()
+                        .map(|m| m.piece_move)
+                        .find(|m| *m == played)
+                });
+            let Some(played) = played else {
+                break forfeit(Termination::IllegalMove(mover));
+            };
+            board.make_move(&played);
+            moves.push(played);
+            comments.push(reply.comment());
+            if let Some(adjudicated) = adjudicator.after_move(&board, mover, reply.score) {
+                break adjudicated;
+            }
+        };
+        let game = PlayedGame {
+            start: opening.start,
+            moves,
+            result,
+            termination,
+        };
+        (game, comments)
+    }
+
+    /// The arguments for `go` and the time the side to move has for its move, if limited.
+    fn go_limits(&self, clocks: &[Duration; 2], mover: Color) -> (String, Option<Duration>) {
+        match self.time_control {
+            TimeControl::Clock { increment, .. } => (
+                format!(
+                    "wtime {} btime {} winc {} binc {}",
+                    clocks[0].as_millis(),
+                    clocks[1].as_millis(),
+                    increment.as_millis(),
+                    increment.as_millis()
+                ),
+                Some(clocks[side_index(mover)]),
+            ),
+            TimeControl::MoveTime(time) => (format!("movetime {}", time.as_millis()), Some(time)),
+            TimeControl::Depth(depth) => (format!("depth {}", depth), None),
+            TimeControl::Nodes(nodes) => (format!("nodes {}", nodes), None),
+        }
+    }
+}
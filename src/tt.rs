@@ -0,0 +1,283 @@
+use std::{
+    mem::size_of,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
+
+use crate::{
+    models::PieceKind,
+    search::{MATE_SCORE, MAX_PLY},
+    UserMove,
+};
+
+pub const DEFAULT_HASH_MB: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TtEntry {
+    pub key: u64,
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<UserMove>,
+    generation: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TtStats {
+    pub probes: u64,
+    pub hits: u64,
+    pub stores: u64,
+    pub capacity: usize,
+    /// Occupancy by the current search in permille, as reported by the UCI `hashfull` field.
+    pub hashfull: usize,
+}
+
+impl TtStats {
+    pub fn hit_rate(&self) -> f64 {
+        if self.probes == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.probes as f64
+        }
+    }
+}
+
+/// A hash table shared by every search thread without locking. Each slot holds the entry packed
+/// into one word next to the key xor-ed with that word, so a slot torn by two threads writing at
+/// once no longer matches its key and reads as empty.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    generation: AtomicU8,
+    probes: AtomicU64,
+    hits: AtomicU64,
+    stores: AtomicU64,
+}
+
+#[derive(Default)]
+struct Slot {
+    checked_key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let mut table = TranspositionTable {
+            slots: Vec::new(),
+            generation: AtomicU8::new(0),
+            probes: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+        };
+        table.resize(size_mb);
+        table
+    }
+
+    pub fn resize(&mut self, size_mb: usize) {
+        let capacity = (size_mb * 1024 * 1024 / size_of::<Slot>()).max(1);
+        self.slots = (0..capacity).map(|_| Slot::default()).collect();
+        self.clear();
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.checked_key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.generation.store(0, Ordering::Relaxed);
+        self.probes.store(0, Ordering::Relaxed);
+        self.hits.store(0, Ordering::Relaxed);
+        self.stores.store(0, Ordering::Relaxed);
+    }
+
+    /// Marks the start of a new search so entries from earlier searches are replaced first.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn probe(&self, key: u64, ply: usize) -> Option<TtEntry> {
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        let mut entry = self.load(self.index(key))?;
+        if entry.key != key {
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        entry.score = score_from_tt(entry.score, ply);
+        Some(entry)
+    }
+
+    pub fn store(
+        &self,
+        key: u64,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        best_move: Option<UserMove>,
+        ply: usize,
+    ) {
+        let index = self.index(key);
+        let generation = self.generation.load(Ordering::Relaxed);
+        let existing = self.load(index);
+        let replace = match &existing {
+            None => true,
+            Some(existing) => {
+                existing.key == key
+                    || existing.generation != generation
+                    || depth >= existing.depth
+                    || bound == Bound::Exact && existing.bound != Bound::Exact
+            }
+        };
+        if !replace {
+            return;
+        }
+        let best_move = match (best_move, &existing) {
+            (None, Some(existing)) if existing.key == key => existing.best_move,
+            _ => best_move,
+        };
+        self.stores.fetch_add(1, Ordering::Relaxed);
+        let data = pack(&TtEntry {
+            key,
+            depth,
+            bound,
+            score: score_to_tt(score, ply),
+            best_move,
+            generation,
+        });
+        let slot = &self.slots[index];
+        slot.checked_key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> TtStats {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let sample = self.slots.len().min(1000);
+        let occupied_in_sample = (0..sample)
+            .filter(|index| {
+                self.load(*index)
+                    .is_some_and(|entry| entry.generation == generation)
+            })
+            .count();
+        TtStats {
+            probes: self.probes.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+            capacity: self.slots.len(),
+            hashfull: occupied_in_sample * 1000 / sample,
+        }
+    }
+
+    fn load(&self, index: usize) -> Option<TtEntry> {
+        let slot = &self.slots[index];
+        let data = slot.data.load(Ordering::Relaxed);
+        let key = slot.checked_key.load(Ordering::Relaxed) ^ data;
+        unpack(key, data)
+    }
+
+    fn index(&self, key: u64) -> usize {
+        ((key as u128 * self.slots.len() as u128) >> 64) as usize
+    }
+}
+
+const OCCUPIED: u64 = 1 << 63;
+const HAS_MOVE: u64 = 1 << 34;
+
+/// Score in bits 0-15, depth 16-23, bound 24-25, generation 26-33, then the move's presence,
+/// origin, destination and promotion from bit 34, and bit 63 set for any stored entry.
+fn pack(entry: &TtEntry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    let mut data = OCCUPIED
+        | entry.score as i16 as u16 as u64
+        | (entry.depth.min(u8::MAX as u32) as u64) << 16
+        | bound << 24
+        | (entry.generation as u64) << 26;
+    if let Some(m) = entry.best_move {
+        let promotion = match m.promotion_request {
+            None => 0,
+            Some(PieceKind::Queen) => 1,
+            Some(PieceKind::Rook) => 2,
+            Some(PieceKind::Bishop) => 3,
+            Some(PieceKind::Knight) => 4,
+            Some(PieceKind::King) => 5,
+            Some(PieceKind::Pawn) => 6,
+        };
+        let origin = (m.origin.0 * 8 + m.origin.1) as u64;
+        let destination = (m.destination.0 * 8 + m.destination.1) as u64;
+        data |= HAS_MOVE | origin << 35 | destination << 41 | promotion << 47;
+    }
+    data
+}
+
+fn unpack(key: u64, data: u64) -> Option<TtEntry> {
+    if data & OCCUPIED == 0 {
+        return None;
+    }
+    let bound = match (data >> 24) & 3 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    let best_move = match data & HAS_MOVE {
+        0 => None,
+        _ => {
+            let square = |bits: u64| ((bits as usize >> 3) & 7, bits as usize & 7);
+            let promotion_request = match (data >> 47) & 7 {
+                1 => Some(PieceKind::Queen),
+                2 => Some(PieceKind::Rook),
+                3 => Some(PieceKind::Bishop),
+                4 => Some(PieceKind::Knight),
+                5 => Some(PieceKind::King),
+                6 => Some(PieceKind::Pawn),
+                _ => None,
+            };
+            Some(UserMove {
+                origin: square((data >> 35) & 63),
+                destination: square((data >> 41) & 63),
+                promotion_request,
+            })
+        }
+    };
+    Some(TtEntry {
+        key,
+        depth: ((data >> 16) & 0xff) as u32,
+        bound,
+        score: data as u16 as i16 as i32,
+        best_move,
+        generation: (data >> 26) as u8,
+    })
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+/// Mate scores are stored relative to the node so they stay correct when reached at another ply.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_SCORE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= -(MATE_SCORE - MAX_PLY as i32) {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_SCORE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -(MATE_SCORE - MAX_PLY as i32) {
+        score + ply as i32
+    } else {
+        score
+    }
+}
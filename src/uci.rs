@@ -0,0 +1,465 @@
+use std::{
+    io::{self, BufRead},
+    sync::{atomic::Ordering, Arc},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    endgame::Tablebase,
+    engine::Engine,
+    models::*,
+    polyglot::PolyglotBook,
+    rng::Rng,
+    search::{SearchInfo, SearchLimits, SearchResult},
+    skill::{Skill, MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO},
+    timeman::Clock,
+    tt::DEFAULT_HASH_MB,
+    Board, MoveOutcome, UserMove,
+};
+
+const ENGINE_NAME: &str = "chess";
+const ENGINE_AUTHOR: &str = "dpatularu";
+const MAX_HASH_MB: usize = 4096;
+const DEFAULT_MOVE_OVERHEAD_MS: u64 = 30;
+const MAX_MOVE_OVERHEAD_MS: u64 = 5000;
+const MAX_MULTI_PV: usize = 256;
+const MAX_THREADS: usize = 256;
+const GO_KEYWORDS: [&str; 12] = [
+    "searchmoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+];
+
+/// Reads UCI commands from stdin until `quit` or end of input.
+pub fn run() {
+    let mut engine = UciEngine::new();
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if !engine.handle_command(&line) {
+            break;
+        }
+    }
+    engine.stop_search();
+}
+
+/// Protocol state driven by UCI commands on top of the shared `Engine`.
+pub struct UciEngine {
+    board: Board,
+    /// The positions the `position` command's moves passed through on the way to `board`.
+    history: Vec<Board>,
+    engine: Engine,
+    hash_mb: usize,
+    move_overhead: Duration,
+    multi_pv: usize,
+    skill_level: u32,
+    limit_strength: bool,
+    elo: u32,
+    own_book: bool,
+    book_file: String,
+    book: Option<PolyglotBook>,
+    rng: Rng,
+}
+
+impl UciEngine {
+    pub fn new() -> Self {
+        UciEngine {
+            board: Board::from_variant(Variant::Standard),
+            history: Vec::new(),
+            engine: Engine::new(),
+            hash_mb: DEFAULT_HASH_MB,
+            move_overhead: Duration::from_millis(DEFAULT_MOVE_OVERHEAD_MS),
+            multi_pv: 1,
+            skill_level: MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: MAX_ELO,
+            own_book: false,
+            book_file: String::new(),
+            book: None,
+            rng: Rng::from_time(),
+        }
+    }
+
+    /// Handles one line of input. Returns false once the engine should exit.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (command, arguments) = match tokens.split_first() {
+            Some((command, arguments)) => (*command, arguments),
+            None => return true,
+        };
+        match command {
+            "uci" => self.identify(),
+            "isready" => println!("readyok"),
+            "ucinewgame" => self.engine.clear_hash(),
+            "position" => {
+                self.stop_search();
+                if let Err(e) = self.set_position(arguments) {
+                    println!("info string {}", e);
+                }
+            }
+            "go" => {
+                self.stop_search();
+                self.go(arguments);
+            }
+            "stop" => self.stop_search(),
+            "ponderhit" => self.engine.ponderhit(),
+            "setoption" => {
+                self.stop_search();
+                if let Err(e) = self.set_option(arguments) {
+                    println!("info string {}", e);
+                }
+            }
+            "d" => println!("{}", self.board.to_fen()),
+            "quit" => return false,
+            _ => println!("info string Unknown command: {}", command),
+        }
+        true
+    }
+
+    fn identify(&self) {
+        println!("id name {} {}", ENGINE_NAME, env!("CARGO_PKG_VERSION"));
+        println!("id author {}", ENGINE_AUTHOR);
+        println!(
+            "option name Hash type spin default {} min 1 max {}",
+            DEFAULT_HASH_MB, MAX_HASH_MB
+        );
+        println!(
+            "option name Threads type spin default 1 min 1 max {}",
+            MAX_THREADS
+        );
+        println!("option name Clear Hash type button");
+        println!("option name Ponder type check default false");
+        println!(
+            "option name Move Overhead type spin default {} min 0 max {}",
+            DEFAULT_MOVE_OVERHEAD_MS, MAX_MOVE_OVERHEAD_MS
+        );
+        println!(
+            "option name MultiPV type spin default 1 min 1 max {}",
+            MAX_MULTI_PV
+        );
+        println!(
+            "option name Skill Level type spin default {} min 0 max {}",
+            MAX_SKILL_LEVEL, MAX_SKILL_LEVEL
+        );
+        println!("option name UCI_LimitStrength type check default false");
+        println!(
+            "option name UCI_Elo type spin default {} min {} max {}",
+            MAX_ELO, MIN_ELO, MAX_ELO
+        );
+        println!("option name OwnBook type check default false");
+        println!("option name Book File type string default <empty>");
+        println!("option name Endgame Path type string default <empty>");
+        println!(
+            "option name UCI_Variant type combo default chess var chess var atomic var antichess var horde var racingkings"
+        );
+        println!("uciok");
+    }
+
+    fn set_position(&mut self, arguments: &[&str]) -> Result<(), String> {
+        let moves_index = arguments.iter().position(|token| *token == "moves");
+        let (setup, moves) = match moves_index {
+            Some(index) => (&arguments[..index], &arguments[index + 1..]),
+            None => (arguments, &[][..]),
+        };
+        let variant = self.board.variant;
+        let mut board = match setup.split_first() {
+            Some((&"startpos", _)) => Board::from_variant(variant),
+            Some((&"fen", fields)) => Board::try_from_fen_with_variant(&fields.join(" "), variant)?,
+            _ => return Err("Expected startpos or fen".to_owned()),
+        };
+        let mut history = Vec::new();
+        for text in moves {
+            let user_move = UserMove::from_uci(text)?;
+            history.push(board);
+            if let MoveOutcome::Error(_) = board.make_move(&user_move) {
+                return Err(format!("Illegal move: {}", text));
+            }
+        }
+        self.board = board;
+        self.history = history;
+        Ok(())
+    }
+
+    fn set_option(&mut self, arguments: &[&str]) -> Result<(), String> {
+        if arguments.first() != Some(&"name") {
+            return Err("Expected setoption name <id> [value <x>]".to_owned());
+        }
+        let value_index = arguments.iter().position(|token| *token == "value");
+        let (name, value) = match value_index {
+            Some(index) => (
+                arguments[1..index].join(" "),
+                arguments[index + 1..].join(" "),
+            ),
+            None => (arguments[1..].join(" "), String::new()),
+        };
+        match name.to_lowercase().as_str() {
+            "hash" => {
+                let size_mb: usize = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Hash value: {}", value))?;
+                self.hash_mb = size_mb.clamp(1, MAX_HASH_MB);
+                self.engine.set_hash_size(self.hash_mb);
+            }
+            "threads" => {
+                let threads: usize = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Threads value: {}", value))?;
+                self.engine.set_threads(threads.clamp(1, MAX_THREADS));
+            }
+            "clear hash" => self.engine.clear_hash(),
+            "ponder" => {}
+            "move overhead" => {
+                let millis: u64 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Move Overhead value: {}", value))?;
+                self.move_overhead = Duration::from_millis(millis.min(MAX_MOVE_OVERHEAD_MS));
+            }
+            "multipv" => {
+                let lines: usize = value
+                    .parse()
+                    .map_err(|_| format!("Invalid MultiPV value: {}", value))?;
+                self.multi_pv = lines.clamp(1, MAX_MULTI_PV);
+            }
+            "skill level" => {
+                let level: u32 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Skill Level value: {}", value))?;
+                self.skill_level = level.min(MAX_SKILL_LEVEL);
+            }
+            "uci_limitstrength" => self.limit_strength = value == "true",
+            "uci_elo" => {
+                let elo: u32 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid UCI_Elo value: {}", value))?;
+                self.elo = elo.clamp(MIN_ELO, MAX_ELO);
+            }
+            "ownbook" => self.own_book = value == "true",
+            "book file" => {
+                self.book_file = value;
+                self.load_book()?;
+            }
+            "endgame path" => {
+                let tablebase = match value.as_str() {
+                    "" | "<empty>" => None,
+                    path => Some(Arc::new(Tablebase::load_dir(path)?)),
+                };
+                self.engine.set_tablebase(tablebase);
+            }
+            "uci_variant" => {
+                let variant = parse_variant(&value)?;
+                self.board = Board::from_variant(variant);
+                self.history.clear();
+            }
+            _ => return Err(format!("Unknown option: {}", name)),
+        }
+        Ok(())
+    }
+
+    fn go(&mut self, arguments: &[&str]) {
+        let (limits, infinite, ponder) = match self.parse_go(arguments) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                println!("info string {}", e);
+                return;
+            }
+        };
+        if !infinite && !ponder {
+            if let Some(book_move) = self.book_move(&limits) {
+                println!("bestmove {}", book_move.to_uci());
+                return;
+            }
+        }
+        let skill = self.skill();
+        let limits = skill.limits(limits);
+        let mut rng = Rng::new(self.rng.next_u64());
+        let stop = self.engine.stop_flag();
+        let ponder_flag = self.engine.ponder_flag();
+        let on_done = move |result: &SearchResult| {
+            // The protocol forbids sending bestmove during an infinite or ponder search before
+            // the GUI has sent stop or ponderhit.
+            while (infinite || ponder_flag.load(Ordering::Relaxed)) && !stop.load(Ordering::Relaxed)
+            {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let result = skill.choose(result.clone(), &mut rng);
+            let ponder_move = result.pv.get(1);
+            match (result.best_move, ponder_move) {
+                (Some(best), Some(ponder)) => {
+                    println!("bestmove {} ponder {}", best.to_uci(), ponder.to_uci())
+                }
+                (Some(best), None) => println!("bestmove {}", best.to_uci()),
+                (None, _) => println!("bestmove 0000"),
+            }
+        };
+        self.engine.set_history(&self.history);
+        self.engine.start(
+            self.board,
+            limits,
+            ponder,
+            Some(Box::new(print_info)),
+            Box::new(on_done),
+        );
+    }
+
+    /// `UCI_LimitStrength` with `UCI_Elo` takes precedence over `Skill Level`.
+    fn skill(&self) -> Skill {
+        match self.limit_strength {
+            true => Skill::from_elo(self.elo),
+            false => Skill::from_level(self.skill_level),
+        }
+    }
+
+    fn parse_go(&self, arguments: &[&str]) -> Result<(SearchLimits, bool, bool), String> {
+        let mut limits = SearchLimits {
+            multi_pv: self.multi_pv,
+            ..Default::default()
+        };
+        let mut infinite = false;
+        let mut ponder = false;
+        let (mut white_time, mut black_time) = (None, None);
+        let (mut white_increment, mut black_increment) = (0, 0);
+        let mut moves_to_go = None;
+
+        let mut index = 0;
+        while index < arguments.len() {
+            let keyword = arguments[index];
+            index += 1;
+            match keyword {
+                "infinite" => infinite = true,
+                "ponder" => ponder = true,
+                "searchmoves" => {
+                    while index < arguments.len() && !GO_KEYWORDS.contains(&arguments[index]) {
+                        limits
+                            .search_moves
+                            .push(self.resolve_move(arguments[index])?);
+                        index += 1;
+                    }
+                }
+                _ => {
+                    let value = arguments
+                        .get(index)
+                        .ok_or(format!("Missing value for {}", keyword))?;
+                    index += 1;
+                    let number: i64 = value
+                        .parse()
+                        .map_err(|_| format!("Invalid value for {}: {}", keyword, value))?;
+                    let unsigned = number.max(0) as u64;
+                    match keyword {
+                        "wtime" => white_time = Some(unsigned),
+                        "btime" => black_time = Some(unsigned),
+                        "winc" => white_increment = unsigned,
+                        "binc" => black_increment = unsigned,
+                        "movestogo" => moves_to_go = Some(unsigned as u32),
+                        "depth" => limits.depth = Some(unsigned as u32),
+                        "nodes" => limits.nodes = Some(unsigned),
+                        "mate" => limits.mate = Some(unsigned as u32),
+                        "movetime" => {
+                            let budget = Duration::from_millis(unsigned)
+                                .saturating_sub(self.move_overhead)
+                                .max(Duration::from_millis(1));
+                            limits.time = Some(budget);
+                        }
+                        _ => return Err(format!("Unknown go parameter: {}", keyword)),
+                    }
+                }
+            }
+        }
+
+        let (remaining, increment) = match self.board.player_turn {
+            Color::White => (white_time, white_increment),
+            Color::Black => (black_time, black_increment),
+        };
+        if let (Some(remaining), false) = (remaining, infinite) {
+            limits.clock = Some(Clock {
+                remaining: Duration::from_millis(remaining),
+                increment: Duration::from_millis(increment),
+                moves_to_go,
+                move_overhead: self.move_overhead,
+            });
+        }
+        Ok((limits, infinite, ponder))
+    }
+
+    /// Matches a UCI move against the legal moves, so that `searchmoves` only keeps moves the
+    /// search can actually play.
+    fn resolve_move(&self, text: &str) -> Result<UserMove, String> {
+        let user_move = UserMove::from_uci(text)?;
+        self.board
+            .legal_moves()
+            .into_iter()
+            .map(|m| m.piece_move)
+            .find(|m| *m == user_move)
+            .ok_or(format!("Illegal move: {}", text))
+    }
+
+    fn load_book(&mut self) -> Result<(), String> {
+        self.book = match self.book_file.as_str() {
+            "" | "<empty>" => None,
+            path => Some(PolyglotBook::open(path)?),
+        };
+        Ok(())
+    }
+
+    fn book_move(&mut self, limits: &SearchLimits) -> Option<UserMove> {
+        if !self.own_book || self.board.variant != Variant::Standard {
+            return None;
+        }
+        let book_move = self.book.as_ref()?.pick(&self.board, &mut self.rng)?;
+        (limits.search_moves.is_empty() || limits.search_moves.contains(&book_move))
+            .then_some(book_move)
+    }
+
+    /// Stops a running search, if any, and waits for its bestmove to be sent.
+    pub fn stop_search(&mut self) {
+        self.engine.stop();
+    }
+}
+
+impl Default for UciEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn print_info(info: &SearchInfo) {
+    let score = match info.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", info.score),
+    };
+    let pv: Vec<String> = info.pv.iter().map(UserMove::to_uci).collect();
+    println!(
+        "info depth {} seldepth {} multipv {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+        info.depth,
+        info.seldepth.max(info.depth),
+        info.multi_pv,
+        score,
+        info.nodes,
+        info.nps(),
+        info.hashfull,
+        info.time.as_millis(),
+        pv.join(" ")
+    );
+}
+
+fn parse_variant(name: &str) -> Result<Variant, String> {
+    match name.to_lowercase().as_str() {
+        "chess" | "standard" => Ok(Variant::Standard),
+        "atomic" => Ok(Variant::Atomic),
+        "antichess" | "giveaway" => Ok(Variant::Antichess),
+        "horde" => Ok(Variant::Horde),
+        "racingkings" => Ok(Variant::RacingKings),
+        _ => Err(format!("Unsupported variant: {}", name)),
+    }
+}
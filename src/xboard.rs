@@ -0,0 +1,477 @@
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    engine::Engine,
+    models::*,
+    search::{SearchInfo, SearchLimits, SearchResult, MATE_SCORE},
+    timeman::Clock,
+    Board, GameStatus, MoveOutcome, UserMove,
+};
+
+const ENGINE_NAME: &str = "chess";
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+const XBOARD_MATE_SCORE: i32 = 100_000;
+
+/// Reads CECP (XBoard/WinBoard protocol version 2) commands from stdin until `quit` or end of
+/// input.
+pub fn run() {
+    let mut engine = XboardEngine::new();
+    let commands = engine.event_sender.clone();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if commands.send(Event::Command(line)).is_err() {
+                return;
+            }
+        }
+        let _ = commands.send(Event::EndOfInput);
+    });
+    while let Ok(event) = engine.events.recv() {
+        match event {
+            Event::Command(line) => {
+                if !engine.handle_command(&line) {
+                    break;
+                }
+            }
+            Event::MoveSent => engine.complete_search(),
+            Event::EndOfInput => break,
+        }
+    }
+    engine.abort_search();
+}
+
+/// What the protocol loop waits for: a line from the GUI or the search sending its move.
+enum Event {
+    Command(String),
+    MoveSent,
+    EndOfInput,
+}
+
+/// The `level` time control: moves per session (0 for the whole game), base time and increment.
+#[derive(Clone, Copy, Debug)]
+struct TimeControl {
+    moves_per_session: u32,
+    base: Duration,
+    increment: Duration,
+}
+
+/// Protocol state driven by XBoard commands on top of the shared `Engine`.
+pub struct XboardEngine {
+    board: Board,
+    /// Positions before each move of the game, for `undo`, `remove` and repetition draws.
+    history: Vec<Board>,
+    engine: Engine,
+    /// The side the engine plays; `None` in force mode.
+    engine_color: Option<Color>,
+    post: bool,
+    time_control: Option<TimeControl>,
+    move_time: Option<Duration>,
+    max_depth: Option<u32>,
+    engine_time: Option<Duration>,
+    /// Set by the search thread once it has sent its move, which then still has to be played on
+    /// `board`.
+    move_sent: Arc<AtomicBool>,
+    /// Tells the search thread to drop its move because the GUI has taken over the position.
+    discard_move: Arc<AtomicBool>,
+    game_over: bool,
+    /// The search thread reports its move here so that `run` plays it straight away.
+    event_sender: Sender<Event>,
+    events: Receiver<Event>,
+}
+
+impl XboardEngine {
+    pub fn new() -> Self {
+        let (event_sender, events) = mpsc::channel();
+        XboardEngine {
+            board: Board::from_variant(Variant::Standard),
+            history: Vec::new(),
+            engine: Engine::new(),
+            engine_color: Some(Color::Black),
+            post: false,
+            time_control: None,
+            move_time: None,
+            max_depth: None,
+            engine_time: None,
+            move_sent: Arc::new(AtomicBool::new(false)),
+            discard_move: Arc::new(AtomicBool::new(false)),
+            game_over: false,
+            event_sender,
+            events,
+        }
+    }
+
+    /// Handles one line of input. Returns false once the engine should exit.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (command, arguments) = match tokens.split_first() {
+            Some((command, arguments)) => (*command, arguments),
+            None => return true,
+        };
+        match command {
+            // Answered while the engine may still be thinking.
+            "?" => {
+                self.finish_search(false);
+                return true;
+            }
+            "ping" => {
+                println!("pong {}", arguments.first().unwrap_or(&""));
+                return true;
+            }
+            "post" => {
+                self.post = true;
+                return true;
+            }
+            "nopost" => {
+                self.post = false;
+                return true;
+            }
+            "time" | "otim" => {
+                if let (true, Some(centiseconds)) = (
+                    command == "time",
+                    arguments
+                        .first()
+                        .and_then(|value| value.parse::<u64>().ok()),
+                ) {
+                    self.engine_time = Some(Duration::from_millis(centiseconds * 10));
+                }
+                return true;
+            }
+            "quit" => return false,
+            _ => {}
+        }
+
+        // Every other command changes the game, so a search still running is abandoned.
+        self.finish_search(true);
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "draw" | "hint" | "bk" | "analyze" | "exit" | "." => {}
+            "protover" => self.announce_features(),
+            "new" => {
+                self.board = Board::from_variant(Variant::Standard);
+                self.history.clear();
+                self.engine_color = Some(Color::Black);
+                self.max_depth = None;
+                self.move_time = None;
+                self.engine_time = None;
+                self.game_over = false;
+                self.engine.clear_hash();
+            }
+            "variant" => match arguments.first().map(|name| parse_variant(name)) {
+                Some(Ok(variant)) => {
+                    self.board = Board::from_variant(variant);
+                    self.history.clear();
+                    self.game_over = false;
+                }
+                Some(Err(e)) => println!("Error (unsupported variant): {}", e),
+                None => println!("Error (missing variant): variant"),
+            },
+            "cores" => match arguments
+                .first()
+                .and_then(|cores| cores.parse::<usize>().ok())
+            {
+                Some(cores) => self.engine.set_threads(cores.max(1)),
+                None => println!("Error (invalid core count): cores"),
+            },
+            "force" => self.engine_color = None,
+            "go" => {
+                self.engine_color = Some(self.board.player_turn);
+                self.think();
+            }
+            "playother" => {
+                self.engine_color = Some(self.board.player_turn.get_opposite_color());
+            }
+            "usermove" => match arguments.first() {
+                Some(text) => self.user_move(text),
+                None => println!("Error (missing move): usermove"),
+            },
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "setboard" => {
+                let fen = arguments.join(" ");
+                match Board::try_from_fen_with_variant(&fen, self.board.variant) {
+                    Ok(board) => {
+                        self.board = board;
+                        self.history.clear();
+                        self.game_over = false;
+                    }
+                    Err(_) => println!("tellusererror Illegal position"),
+                }
+            }
+            "level" => match parse_level(arguments) {
+                Ok(time_control) => {
+                    self.time_control = Some(time_control);
+                    self.move_time = None;
+                }
+                Err(e) => println!("Error ({}): level", e),
+            },
+            "st" => match arguments
+                .first()
+                .and_then(|value| value.parse::<f64>().ok())
+            {
+                Some(seconds) if seconds > 0.0 => {
+                    self.move_time = Some(Duration::from_secs_f64(seconds));
+                    self.time_control = None;
+                }
+                _ => println!("Error (invalid time): st"),
+            },
+            "sd" => match arguments
+                .first()
+                .and_then(|value| value.parse::<u32>().ok())
+            {
+                Some(depth) if depth > 0 => self.max_depth = Some(depth),
+                _ => println!("Error (invalid depth): sd"),
+            },
+            "result" => {
+                self.engine_color = None;
+                self.game_over = true;
+            }
+            _ => {
+                // Protocol version 2 GUIs send moves with `usermove`, older ones send them bare.
+                if UserMove::from_uci(command).is_ok() {
+                    self.user_move(command);
+                } else {
+                    println!("Error (unknown command): {}", command);
+                }
+            }
+        }
+        true
+    }
+
+    fn announce_features(&self) {
+        println!(
+            "feature myname=\"{} {}\" ping=1 setboard=1 usermove=1 playother=1 san=0 time=1 \
+             draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 smp=1 \
+             variants=\"normal,atomic,giveaway,horde,racingkings\" done=1",
+            ENGINE_NAME,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    fn user_move(&mut self, text: &str) {
+        if self.game_over {
+            println!("Illegal move (game is over): {}", text);
+            return;
+        }
+        let user_move = match UserMove::from_uci(text) {
+            Ok(user_move) => user_move,
+            Err(_) => {
+                println!("Error (unknown command): {}", text);
+                return;
+            }
+        };
+        if !self.play(&user_move) {
+            println!("Illegal move: {}", text);
+            return;
+        }
+        if self.engine_color == Some(self.board.player_turn) {
+            self.think();
+        }
+    }
+
+    /// Plays a move on the game board, reporting the result if it ends the game.
+    fn play(&mut self, user_move: &UserMove) -> bool {
+        let before = self.board;
+        if let MoveOutcome::Error(_) = self.board.make_move(user_move) {
+            return false;
+        }
+        self.history.push(before);
+        if let Some(result) = self.result() {
+            println!("{}", result);
+            self.game_over = true;
+        }
+        true
+    }
+
+    fn take_back(&mut self, moves: usize) {
+        if moves > self.history.len() {
+            println!("Error (no moves to undo): {}", moves);
+            return;
+        }
+        for _ in 0..moves {
+            if let Some(board) = self.history.pop() {
+                self.board = board;
+            }
+        }
+        self.game_over = false;
+    }
+
+    fn think(&mut self) {
+        if self.game_over {
+            return;
+        }
+        let limits = self.search_limits();
+        self.engine.set_history(&self.history);
+        let post = self.post;
+        let on_info = move |info: &SearchInfo| {
+            if post {
+                print_thinking(info);
+            }
+        };
+        let move_sent = self.move_sent.clone();
+        let discard_move = self.discard_move.clone();
+        let events = self.event_sender.clone();
+        move_sent.store(false, Ordering::Relaxed);
+        discard_move.store(false, Ordering::Relaxed);
+        let on_done = move |result: &SearchResult| {
+            if discard_move.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Some(best) = result.best_move {
+                println!("move {}", best.to_uci());
+                move_sent.store(true, Ordering::Relaxed);
+                let _ = events.send(Event::MoveSent);
+            }
+        };
+        self.engine.start(
+            self.board,
+            limits,
+            false,
+            Some(Box::new(on_info)),
+            Box::new(on_done),
+        );
+    }
+
+    fn search_limits(&self) -> SearchLimits {
+        let mut limits = SearchLimits {
+            depth: self.max_depth,
+            time: self.move_time,
+            ..Default::default()
+        };
+        if let Some(time_control) = self.time_control {
+            let moves_to_go = match time_control.moves_per_session {
+                0 => None,
+                session => {
+                    let played = (self.board.num_moves as u32).saturating_sub(1);
+                    Some(session - played % session)
+                }
+            };
+            limits.clock = Some(Clock {
+                remaining: self.engine_time.unwrap_or(time_control.base),
+                increment: time_control.increment,
+                moves_to_go,
+                move_overhead: MOVE_OVERHEAD,
+            });
+        }
+        limits
+    }
+
+    /// Stops a running search, discarding its move unless the search is told to move now.
+    fn finish_search(&mut self, discard: bool) {
+        self.discard_move.store(discard, Ordering::Relaxed);
+        let result = self.engine.stop();
+        // A move that was sent before the search noticed the request still has to be played.
+        if self.move_sent.swap(false, Ordering::Relaxed) {
+            if let Some(best) = result.and_then(|result| result.best_move) {
+                self.play(&best);
+            }
+        }
+    }
+
+    /// Plays the move the search has just sent, announcing the result if it ends the game. A
+    /// GUI command handled in between may already have played it.
+    pub fn complete_search(&mut self) {
+        if self.move_sent.load(Ordering::Relaxed) {
+            self.finish_search(false);
+        }
+    }
+
+    pub fn abort_search(&mut self) {
+        self.finish_search(true);
+    }
+
+    /// The PGN result line for a finished game, with threefold repetition checked against the
+    /// game history.
+    fn result(&self) -> Option<String> {
+        let status = self.board.game_status();
+        let hash = self.board.hash();
+        let repetitions = self
+            .history
+            .iter()
+            .filter(|board| board.hash() == hash)
+            .count();
+        let result = match status {
+            GameStatus::Checkmate(Color::White) => "1-0 {White mates}",
+            GameStatus::Checkmate(Color::Black) => "0-1 {Black mates}",
+            GameStatus::VariantWin(Color::White) => "1-0 {White wins}",
+            GameStatus::VariantWin(Color::Black) => "0-1 {Black wins}",
+            GameStatus::Draw => "1/2-1/2 {Draw}",
+            GameStatus::Ongoing if repetitions >= 2 => "1/2-1/2 {Draw by repetition}",
+            GameStatus::Ongoing => return None,
+        };
+        Some(result.to_owned())
+    }
+}
+
+impl Default for XboardEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thinking output in the `ply score time nodes pv` format, with the time in centiseconds and
+/// mates as 100000 + N.
+fn print_thinking(info: &SearchInfo) {
+    let score = match info.mate_in() {
+        Some(moves) if moves > 0 => XBOARD_MATE_SCORE + moves,
+        Some(moves) => -XBOARD_MATE_SCORE + moves,
+        None => info.score.clamp(-MATE_SCORE, MATE_SCORE),
+    };
+    let pv: Vec<String> = info.pv.iter().map(UserMove::to_uci).collect();
+    println!(
+        "{} {} {} {} {}",
+        info.depth,
+        score,
+        info.time.as_millis() / 10,
+        info.nodes,
+        pv.join(" ")
+    );
+}
+
+fn parse_variant(name: &str) -> Result<Variant, String> {
+    match name {
+        "normal" => Ok(Variant::Standard),
+        "atomic" => Ok(Variant::Atomic),
+        "giveaway" | "suicide" => Ok(Variant::Antichess),
+        "horde" => Ok(Variant::Horde),
+        "racingkings" => Ok(Variant::RacingKings),
+        _ => Err(name.to_owned()),
+    }
+}
+
+/// Parses `level MPS BASE INC`, where BASE is minutes or `minutes:seconds` and INC is seconds.
+fn parse_level(arguments: &[&str]) -> Result<TimeControl, String> {
+    let (moves, base, increment) = match arguments {
+        [moves, base, increment] => (moves, base, increment),
+        _ => return Err("expected MPS BASE INC".to_owned()),
+    };
+    let moves_per_session: u32 = moves.parse().map_err(|_| "invalid MPS".to_owned())?;
+    let base_seconds = match base.split_once(':') {
+        Some((minutes, seconds)) => {
+            let minutes: u64 = minutes.parse().map_err(|_| "invalid BASE".to_owned())?;
+            let seconds: u64 = seconds.parse().map_err(|_| "invalid BASE".to_owned())?;
+            minutes * 60 + seconds
+        }
+        None => base.parse::<u64>().map_err(|_| "invalid BASE".to_owned())? * 60,
+    };
+    let increment: f64 = increment.parse().map_err(|_| "invalid INC".to_owned())?;
+    if increment < 0.0 {
+        return Err("invalid INC".to_owned());
+    }
+    Ok(TimeControl {
+        moves_per_session,
+        base: Duration::from_secs(base_seconds),
+        increment: Duration::from_secs_f64(increment),
+    })
+}
@@ -0,0 +1,72 @@
+use crate::{models::*, rng::Rng, Board};
+
+const PIECE_KEYS: [[u64; 64]; 12] = generate_piece_keys();
+const SIDE_KEY: u64 = Rng::new(0x5eed_0000_0000_0001).next_u64();
+const CASTLE_KEYS: [u64; 4] = generate_keys::<4>(0x5eed_0000_0000_0002);
+const EN_PASSANT_KEYS: [u64; 8] = generate_keys::<8>(0x5eed_0000_0000_0003);
+
+const fn generate_keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut rng = Rng::new(seed);
+    let mut i = 0;
+    while i < N {
+        keys[i] = rng.next_u64();
+        i += 1;
+    }
+    keys
+}
+
+const fn generate_piece_keys() -> [[u64; 64]; 12] {
+    let mut keys = [[0; 64]; 12];
+    let mut piece = 0;
+    while piece < 12 {
+        keys[piece] = generate_keys::<64>(0x5eed_0000_0001_0000 + piece as u64);
+        piece += 1;
+    }
+    keys
+}
+
+fn piece_key(piece: &Piece, (row, column): (usize, usize)) -> u64 {
+    let kind = match piece.kind {
+        PieceKind::King => 0,
+        PieceKind::Queen => 1,
+        PieceKind::Rook => 2,
+        PieceKind::Bishop => 3,
+        PieceKind::Knight => 4,
+        PieceKind::Pawn => 5,
+    };
+    let color = match piece.color {
+        Color::White => 0,
+        Color::Black => 6,
+    };
+    PIECE_KEYS[kind + color][row * 8 + column]
+}
+
+pub fn hash(board: &Board) -> u64 {
+    let mut key = 0;
+    for (row, pieces) in board.board.iter().enumerate() {
+        for (column, square) in pieces.iter().enumerate() {
+            if let Some(piece) = square {
+                key ^= piece_key(piece, (row, column));
+            }
+        }
+    }
+    if board.player_turn == Color::Black {
+        key ^= SIDE_KEY;
+    }
+    let rights = [
+        board.castle_rights.white_king_side,
+        board.castle_rights.white_queen_side,
+        board.castle_rights.black_king_side,
+        board.castle_rights.black_queen_side,
+    ];
+    for (right, castle_key) in rights.iter().zip(CASTLE_KEYS) {
+        if *right {
+            key ^= castle_key;
+        }
+    }
+    if let Some((_, column)) = board.en_passant_square {
+        key ^= EN_PASSANT_KEYS[column];
+    }
+    key
+}
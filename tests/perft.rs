@@ -0,0 +1,64 @@
+use chess::{
+    models::{PieceKind, Variant},
+    Board, UserMove,
+};
+
+/// Counts the leaf positions `depth` plies below `board`. The start positions checked here reach
+/// no promotions at these depths, so one move per origin and destination is enough.
+fn perft(board: &Board, depth: usize) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+    board
+        .valid_moves()
+        .iter()
+        .map(|m| {
+            let mut child = *board;
+            child.make_move(&UserMove::new(
+                m.origin,
+                m.destination,
+                Some(PieceKind::Queen),
+            ));
+            perft(&child, depth - 1)
+        })
+        .sum()
+}
+
+fn assert_perft(variant: Variant, expected: &[usize]) {
+    let board = Board::from_variant(variant);
+    for (depth, nodes) in expected.iter().enumerate() {
+        assert_eq!(
+            perft(&board, depth + 1),
+            *nodes,
+            "{:?} depth {}",
+            variant,
+            depth + 1
+        );
+    }
+}
+
+#[test]
+fn standard_start_position() {
+    assert_perft(Variant::Standard, &[20, 400, 8902]);
+}
+
+/// Explosions first change the count at depth 4.
+#[test]
+fn atomic_start_position() {
+    assert_perft(Variant::Atomic, &[20, 400, 8902, 197326]);
+}
+
+#[test]
+fn antichess_start_position() {
+    assert_perft(Variant::Antichess, &[20, 400, 8067, 153299]);
+}
+
+#[test]
+fn horde_start_position() {
+    assert_perft(Variant::Horde, &[8, 128, 1274, 23310]);
+}
+
+#[test]
+fn racing_kings_start_position() {
+    assert_perft(Variant::RacingKings, &[21, 421, 11264]);
+}
@@ -0,0 +1,10 @@
+use chess::pgn::parse_games;
+
+#[test]
+fn stray_closing_brackets_fail_only_their_game() {
+    let games = parse_games("1. e4 } e5 *\n\n[Event \"x\"]] 1. e4 *\n\n1. d4 d5 *\n");
+    assert_eq!(games.len(), 3);
+    assert!(games[0].is_err());
+    assert!(games[1].is_err());
+    assert_eq!(games[2].as_ref().map(|game| game.moves.len()), Ok(2));
+}
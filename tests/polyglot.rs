@@ -0,0 +1,22 @@
+use chess::{models::Variant, polyglot, Board, UserMove};
+
+#[test]
+fn hashes_match_the_published_polyglot_keys() {
+    let expected = [
+        (vec![], 0x463b_9618_1691_fc9c),
+        (vec!["e2e4"], 0x823c_9b50_fd11_4196),
+        (vec!["e2e4", "d7d5"], 0x0756_b944_61c5_0fb0),
+        (vec!["e2e4", "d7d5", "e4e5", "f7f5"], 0x22a4_8b5a_8e47_ff78),
+        (
+            vec!["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"],
+            0x652a_607c_a3f2_42c1,
+        ),
+    ];
+    for (moves, key) in expected {
+        let mut board = Board::from_variant(Variant::Standard);
+        for text in &moves {
+            board.make_move(&UserMove::from_uci(text).unwrap());
+        }
+        assert_eq!(polyglot::hash(&board), key, "after {:?}", moves);
+    }
+}
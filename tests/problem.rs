@@ -0,0 +1,25 @@
+use chess::{models::Variant, problem::Stipulation, Board};
+
+#[test]
+fn solves_legals_mate_in_two() {
+    let board = Board::try_from_fen_with_variant(
+        "rn1qkbnr/ppp2p1p/3p2p1/4N3/2B1P3/2N5/PPPP1PPP/R1BbK2R w KQkq - 0 6",
+        Variant::Standard,
+    )
+    .unwrap();
+    let report = board
+        .solve_problem(Stipulation::parse("#2").unwrap())
+        .unwrap();
+    assert!(report.is_sound());
+    assert_eq!(report.keys.len(), 1);
+    let key = &report.keys[0];
+    assert_eq!(key.san, "Bxf7+");
+    assert_eq!(key.defences.len(), 1);
+    assert_eq!(key.defences[0].san, "Ke7");
+    let continuations: Vec<&str> = key.defences[0]
+        .continuations
+        .iter()
+        .map(|(_, san)| san.as_str())
+        .collect();
+    assert_eq!(continuations, ["Nd5#"]);
+}